@@ -1,8 +1,47 @@
-use librojo::cli;
+use std::path::{Path, PathBuf};
+
+#[cfg(not(any(feature = "build-plugin", feature = "prebuilt-plugin")))]
+compile_error!("Enable either the `build-plugin` or the `prebuilt-plugin` feature");
 
 fn main() {
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
-    let dest_path = std::path::PathBuf::from(&out_dir).join("MCPStudioPlugin.rbxm");
+    let dest_path = PathBuf::from(&out_dir).join("MCPStudioPlugin.rbxm");
+    if cfg!(feature = "prebuilt-plugin") {
+        copy_prebuilt_plugin(&dest_path);
+    } else {
+        #[cfg(feature = "build-plugin")]
+        build_plugin(dest_path);
+    }
+}
+
+/// Embeds an already built plugin instead of running rojo, e.g. one downloaded from a release.
+fn copy_prebuilt_plugin(dest_path: &Path) {
+    println!("cargo:rerun-if-env-changed=RBX_PREBUILT_PLUGIN");
+    let source = PathBuf::from(
+        std::env::var_os("RBX_PREBUILT_PLUGIN").unwrap_or_else(|| "MCPStudioPlugin.rbxm".into()),
+    );
+    println!("cargo:rerun-if-changed={}", source.display());
+    if let Err(err) = std::fs::copy(&source, dest_path) {
+        println!(
+            "cargo:warning=Failed to copy the prebuilt plugin from {}: {err}",
+            source.display()
+        );
+        eprintln!(
+            "The prebuilt-plugin feature expects a plugin at {}, set RBX_PREBUILT_PLUGIN to use another path",
+            source.display()
+        );
+        std::process::exit(1);
+    }
+}
+
+#[cfg(feature = "build-plugin")]
+fn build_plugin(dest_path: PathBuf) {
+    use librojo::cli;
+
+    println!("cargo:rerun-if-env-changed=RBX_PLUGIN_DIR");
+    // Forks can point the build at a customized plugin without patching this file.
+    let project =
+        PathBuf::from(std::env::var_os("RBX_PLUGIN_DIR").unwrap_or_else(|| "plugin".into()));
     eprintln!("Rebuilding plugin: {dest_path:?}");
     let options = cli::Options {
         global: cli::GlobalOptions {
@@ -10,12 +49,26 @@ fn main() {
             color: cli::ColorChoice::Always,
         },
         subcommand: cli::Subcommand::Build(cli::BuildCommand {
-            project: std::path::PathBuf::from("plugin"),
+            project: project.clone(),
             output: Some(dest_path),
             plugin: None,
             watch: false,
         }),
     };
-    options.run().unwrap();
-    println!("cargo:rerun-if-changed=plugin");
+    if let Err(err) = options.run() {
+        // Surface the whole error chain, the file rojo choked on is usually a few causes down.
+        println!(
+            "cargo:warning=Failed to build the Studio plugin from {}",
+            project.display()
+        );
+        for cause in err.chain() {
+            println!("cargo:warning={cause}");
+        }
+        eprintln!(
+            "Rojo failed to build the Studio plugin in {}: {err:#}",
+            project.display()
+        );
+        std::process::exit(1);
+    }
+    println!("cargo:rerun-if-changed={}", project.display());
 }
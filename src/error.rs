@@ -38,3 +38,119 @@ impl IntoResponse for Report {
             .into_response()
     }
 }
+
+/// Kind of failure reported back to the MCP client as part of a tool result. Each kind has a
+/// stable string name and a JSON-RPC style numeric code, reusing the standard codes where one fits
+/// and the implementation defined server error range otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorCode {
+    /// The plugin ran the command but it failed inside Studio.
+    PluginError,
+    /// The arguments were rejected by the server before anything was sent to Studio.
+    InvalidArguments,
+    /// The tool was called too often and the command was dropped without running.
+    RateLimited,
+    /// Studio didn't reply before the tool call timed out.
+    Timeout,
+    /// Too many commands are already waiting for Studio to pick them up.
+    QueueFull,
+    /// The command was cancelled by the user before it finished.
+    Cancelled,
+    /// The tool is turned off by the server's configuration, e.g. `--read-only`.
+    Disabled,
+    /// Studio isn't connected and the server was told not to wait for it with `--no-wait`.
+    NotConnected,
+    /// The tool changes the place and Studio is running it in Run mode, see
+    /// `--allow-edits-while-running`.
+    PlaceRunning,
+    /// The plugin has no tool for the command, usually because it is older than the server.
+    UnknownVariant,
+    /// The command never reached Studio or its reply was lost.
+    Internal,
+}
+
+impl ToolErrorCode {
+    /// Stable name of this kind of failure, the same as its serialized form.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PluginError => "plugin_error",
+            Self::InvalidArguments => "invalid_arguments",
+            Self::RateLimited => "rate_limited",
+            Self::Timeout => "timeout",
+            Self::QueueFull => "queue_full",
+            Self::Cancelled => "cancelled",
+            Self::Disabled => "disabled",
+            Self::NotConnected => "not_connected",
+            Self::PlaceRunning => "place_running",
+            Self::UnknownVariant => "unknown_variant",
+            Self::Internal => "internal",
+        }
+    }
+
+    /// JSON-RPC style numeric code for this kind of failure.
+    pub fn rpc_code(self) -> i32 {
+        match self {
+            Self::PluginError => -32000,
+            Self::RateLimited => -32001,
+            Self::Timeout => -32002,
+            Self::QueueFull => -32003,
+            Self::Cancelled => -32004,
+            Self::Disabled => -32005,
+            Self::NotConnected => -32006,
+            Self::PlaceRunning => -32007,
+            Self::InvalidArguments => -32602,
+            Self::UnknownVariant => -32601,
+            Self::Internal => -32603,
+        }
+    }
+}
+
+/// Failure of a single tool call, serialized as `{code, rpc_code, message}` so the model can
+/// branch on `code` instead of parsing prose.
+#[derive(Debug, Clone)]
+pub struct ToolError {
+    pub code: ToolErrorCode,
+    pub message: String,
+}
+
+impl ToolError {
+    pub fn new(code: ToolErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+    pub fn invalid_arguments(message: impl Into<String>) -> Self {
+        Self::new(ToolErrorCode::InvalidArguments, message)
+    }
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ToolErrorCode::Internal, message)
+    }
+}
+
+impl serde::Serialize for ToolError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut body = serializer.serialize_struct("ToolError", 3)?;
+        body.serialize_field("code", &self.code)?;
+        body.serialize_field("rpc_code", &self.code.rpc_code())?;
+        body.serialize_field("message", &self.message)?;
+        body.end()
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+impl From<ToolError> for rmcp::model::CallToolResult {
+    fn from(err: ToolError) -> Self {
+        let body = serde_json::to_string(&err).unwrap_or_else(|_| err.message.clone());
+        Self::error(vec![rmcp::model::Content::text(body)])
+    }
+}
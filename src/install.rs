@@ -10,6 +10,13 @@ use std::path::PathBuf;
 use std::vec;
 use std::{env, fs, io};
 
+/// Prints a line of installer output for the user, the only place allowed to write to stdout.
+/// The installer never runs alongside the stdio server, so this can't corrupt the protocol.
+#[allow(clippy::print_stdout)]
+fn report(line: &str) {
+    println!("{line}");
+}
+
 fn get_message(successes: String) -> String {
     format!("Roblox Studio MCP is ready to go.
 Please restart Studio and MCP clients to apply the changes.
@@ -99,7 +106,9 @@ pub fn install_to_config<'a>(
     file.write_all(serde_json::to_string_pretty(&config)?.as_bytes())
         .map_err(|e| eyre!("Could not write to {name} config file at {config_path:?}: {e:#?}"))?;
 
-    println!("Installed MCP Studio plugin to {name} config {config_path:?}");
+    report(&format!(
+        "Installed MCP Studio plugin to {name} config {config_path:?}"
+    ));
 
     Ok(name)
 }
@@ -123,10 +132,10 @@ async fn install_internal() -> Result<String> {
         })?;
         file.write_all(plugin_bytes)?;
     }
-    println!(
+    report(&format!(
         "Installed Roblox Studio plugin to {}",
         output_plugin.display()
-    );
+    ));
 
     let this_exe = get_exe_path()?;
 
@@ -149,14 +158,26 @@ async fn install_internal() -> Result<String> {
         return Err(error);
     }
 
-    println!();
+    report("");
     let msg = get_message(successes.join("\n"));
-    println!("{msg}");
+    report(&msg);
     Ok(msg)
 }
 
+/// Installs the plugin and configures the MCP clients. Unless `non_interactive` is set the result
+/// is shown in a way that waits for the user, a pause on Windows and a dialog on macOS, since the
+/// installer is usually started by double clicking it. Non-interactive installs only report to
+/// stdout and stderr and fail with an error, for provisioning machines unattended.
+pub async fn install(non_interactive: bool) -> Result<()> {
+    if non_interactive {
+        install_internal().await?;
+        return Ok(());
+    }
+    install_interactive().await
+}
+
 #[cfg(target_os = "windows")]
-pub async fn install() -> Result<()> {
+async fn install_interactive() -> Result<()> {
     use std::process::Command;
     if let Err(e) = install_internal().await {
         tracing::error!("Failed initialize Roblox MCP: {:#}", e);
@@ -166,7 +187,7 @@ pub async fn install() -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-pub async fn install() -> Result<()> {
+async fn install_interactive() -> Result<()> {
     use native_dialog::{DialogBuilder, MessageLevel};
     let alert_builder = match install_internal().await {
         Err(e) => DialogBuilder::message()
@@ -181,7 +202,7 @@ pub async fn install() -> Result<()> {
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-pub async fn install() -> Result<()> {
+async fn install_interactive() -> Result<()> {
     install_internal().await?;
     Ok(())
 }
@@ -0,0 +1,291 @@
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// The journal is compacted down to the unfinished commands after this many commands finished,
+/// so a long running server doesn't grow it without bound.
+const COMPACT_AFTER_FINISHED: usize = 1000;
+
+/// One line of the journal, commands are written when queued and marked finished once their
+/// caller got a reply or gave up.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Entry<T> {
+    Queued { id: Uuid, at: u64, command: T },
+    Finished { id: Uuid, at: u64 },
+}
+
+/// Append-only JSON lines log of queued commands, so the commands that were in flight when the
+/// server died can be inspected and replayed on the next start.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    /// Commands queued and not finished yet in the order they were queued, the journal is
+    /// compacted down to them.
+    pending: Vec<(Uuid, u64, Value)>,
+    finished_since_compaction: usize,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, returning the commands that never finished in the order they
+    /// were queued. The file is compacted down to those commands.
+    pub fn open<T: Serialize + DeserializeOwned>(path: &Path) -> Result<(Self, Vec<(Uuid, T)>)> {
+        let mut pending: Vec<(Uuid, u64, Value)> = Vec::new();
+        match File::open(path) {
+            Ok(file) => {
+                for (number, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line
+                        .wrap_err_with(|| format!("Could not read journal {}", path.display()))?;
+                    match serde_json::from_str::<Entry<Value>>(&line) {
+                        Ok(Entry::Queued { id, at, command }) => pending.push((id, at, command)),
+                        Ok(Entry::Finished { id, .. }) => {
+                            pending.retain(|(queued, _, _)| *queued != id)
+                        }
+                        // A crash can leave a torn last line behind.
+                        Err(e) => tracing::warn!(
+                            "Skipping line {} of journal {}: {e}",
+                            number + 1,
+                            path.display()
+                        ),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e)
+                    .wrap_err_with(|| format!("Could not read journal {}", path.display()))
+            }
+        }
+
+        let mut commands = Vec::new();
+        pending.retain(|(id, _, command)| match T::deserialize(command) {
+            Ok(command) => {
+                commands.push((*id, command));
+                true
+            }
+            Err(e) => {
+                tracing::warn!("Dropping journaled command {id} this version can't run: {e}");
+                false
+            }
+        });
+
+        let file = compact(path, &pending)?;
+        let journal = Self {
+            path: path.to_path_buf(),
+            file,
+            pending,
+            finished_since_compaction: 0,
+        };
+        Ok((journal, commands))
+    }
+
+    pub fn queued<T: Serialize>(&mut self, id: Uuid, command: &T) {
+        let command = match serde_json::to_value(command) {
+            Ok(command) => command,
+            Err(e) => {
+                tracing::warn!("Failed to journal command {id}: {e}");
+                return;
+            }
+        };
+        let at = now();
+        self.append(&Entry::Queued {
+            id,
+            at,
+            command: &command,
+        });
+        self.pending.push((id, at, command));
+    }
+
+    /// Marks a command finished. Commands that already finished or were never journaled are
+    /// ignored, a late reply for a command whose caller gave up doesn't mark it twice.
+    pub fn finished(&mut self, id: Uuid) {
+        let Some(index) = self.pending.iter().position(|(queued, _, _)| *queued == id) else {
+            return;
+        };
+        self.pending.remove(index);
+        self.append(&Entry::<()>::Finished { id, at: now() });
+        self.finished_since_compaction += 1;
+        if self.finished_since_compaction >= COMPACT_AFTER_FINISHED {
+            match compact(&self.path, &self.pending) {
+                Ok(file) => {
+                    self.file = file;
+                    self.finished_since_compaction = 0;
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to compact journal {}: {e:?}", self.path.display())
+                }
+            }
+        }
+    }
+
+    /// Journaling is best effort, a failed write is logged rather than failing the tool call.
+    fn append<T: Serialize>(&mut self, entry: &Entry<T>) {
+        let written = serde_json::to_string(entry)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.file, "{line}"));
+        if let Err(e) = written {
+            tracing::warn!("Failed to write journal {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Replaces the journal at `path` with just the `pending` commands, returning it opened for
+/// appending. The new file is written next to it and renamed over it, so a crash can't lose it.
+fn compact(path: &Path, pending: &[(Uuid, u64, Value)]) -> Result<File> {
+    let compacted = path.with_extension("compacting");
+    {
+        let mut file = File::create(&compacted)
+            .wrap_err_with(|| format!("Could not write journal {}", compacted.display()))?;
+        for (id, at, command) in pending {
+            let entry = Entry::Queued {
+                id: *id,
+                at: *at,
+                command,
+            };
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        file.sync_all()?;
+    }
+    std::fs::rename(&compacted, path)
+        .wrap_err_with(|| format!("Could not replace journal {}", path.display()))?;
+
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("Could not open journal {}", path.display()))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path of a journal in a fresh directory, removed again when dropped.
+    struct TempJournal(PathBuf);
+
+    impl TempJournal {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("rbx-studio-mcp-{}", Uuid::new_v4()));
+            std::fs::create_dir(&dir).unwrap();
+            Self(dir.join("journal.jsonl"))
+        }
+
+        fn lines(&self) -> Vec<String> {
+            std::fs::read_to_string(&self.0)
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    impl Drop for TempJournal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(self.0.parent().unwrap());
+        }
+    }
+
+    fn open(path: &TempJournal) -> (Journal, Vec<(Uuid, String)>) {
+        Journal::open(&path.0).unwrap()
+    }
+
+    #[test]
+    fn reopening_returns_unfinished_commands_in_order() {
+        let path = TempJournal::new();
+        let (mut journal, commands) = open(&path);
+        assert!(commands.is_empty());
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for (id, command) in ids.iter().zip(["first", "second", "third"]) {
+            journal.queued(*id, &command);
+        }
+        journal.finished(ids[1]);
+        drop(journal);
+
+        let (_, commands) = open(&path);
+        assert_eq!(
+            commands,
+            [(ids[0], "first".to_string()), (ids[2], "third".to_string())]
+        );
+        // Compacted down to the two unfinished commands.
+        assert_eq!(path.lines().len(), 2);
+    }
+
+    #[test]
+    fn torn_lines_are_skipped() {
+        let path = TempJournal::new();
+        let (mut journal, _) = open(&path);
+        let id = Uuid::new_v4();
+        journal.queued(id, &"kept");
+        drop(journal);
+        let mut file = OpenOptions::new().append(true).open(&path.0).unwrap();
+        write!(
+            file,
+            r#"{{"event":"queued","id":"{}","at":1,"comm"#,
+            Uuid::new_v4()
+        )
+        .unwrap();
+        drop(file);
+
+        let (mut journal, commands) = open(&path);
+        assert_eq!(commands, [(id, "kept".to_string())]);
+        // The torn line is gone, so commands journaled after it parse again.
+        let next = Uuid::new_v4();
+        journal.queued(next, &"next");
+        drop(journal);
+        let (_, commands) = open(&path);
+        assert_eq!(
+            commands,
+            [(id, "kept".to_string()), (next, "next".to_string())]
+        );
+    }
+
+    #[test]
+    fn commands_are_marked_finished_once() {
+        let path = TempJournal::new();
+        let (mut journal, _) = open(&path);
+        let id = Uuid::new_v4();
+        journal.queued(id, &"command");
+        journal.finished(id);
+        journal.finished(id);
+        journal.finished(Uuid::new_v4());
+        assert_eq!(path.lines().len(), 2);
+    }
+
+    #[test]
+    fn finished_commands_are_compacted_away() {
+        let path = TempJournal::new();
+        let (mut journal, _) = open(&path);
+        let unfinished = Uuid::new_v4();
+        journal.queued(unfinished, &"unfinished");
+        for _ in 0..COMPACT_AFTER_FINISHED {
+            let id = Uuid::new_v4();
+            journal.queued(id, &"finished");
+            journal.finished(id);
+        }
+        assert_eq!(path.lines().len(), 1);
+
+        // Appending carries on in the compacted file.
+        let id = Uuid::new_v4();
+        journal.queued(id, &"after");
+        drop(journal);
+        let (_, commands) = open(&path);
+        assert_eq!(
+            commands,
+            [
+                (unfinished, "unfinished".to_string()),
+                (id, "after".to_string())
+            ]
+        );
+    }
+}
@@ -1,45 +1,222 @@
-use axum::routing::{get, post};
+// Stdout carries the MCP protocol when serving over stdio, anything else written there breaks
+// the client. Output meant for the user goes through `install::report` or to stderr.
+#![deny(clippy::print_stdout)]
+
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, WrapErr};
 use rbx_studio_server::*;
 use rmcp::ServiceExt;
 use std::io;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing_subscriber::{self, EnvFilter};
+use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
 mod error;
 mod install;
+mod journal;
+mod mock_plugin;
+mod rate_limit;
 mod rbx_studio_server;
+mod roblox_types;
+mod schema_summary;
+#[cfg(feature = "otlp")]
+mod telemetry;
 
 /// Simple MCP proxy for Roblox Studio
 /// Run without arguments to install the plugin
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    after_help = "Every flag can also be set with the RBX_ environment variable shown next to it. \
+Flags given on the command line override the environment, which overrides the defaults."
+)]
 struct Args {
     /// Run as MCP server on stdio
-    #[arg(short, long)]
+    #[arg(short, long, env = "RBX_STDIO")]
     stdio: bool,
+
+    /// Instructions sent to the MCP client, replacing the default description of the tools
+    #[arg(long, conflicts_with = "instructions_file", env = "RBX_INSTRUCTIONS")]
+    instructions: Option<String>,
+
+    /// Read the instructions sent to the MCP client from a file
+    #[arg(long, value_name = "PATH", env = "RBX_INSTRUCTIONS_FILE")]
+    instructions_file: Option<PathBuf>,
+
+    /// Server name shown by MCP clients in their list of connected servers
+    #[arg(long, default_value = DEFAULT_SERVER_NAME, env = "RBX_SERVER_NAME")]
+    server_name: String,
+
+    /// Maximum calls per second for each tool before calls are rejected, 0 disables the limit
+    #[arg(long, default_value_t = DEFAULT_RATE_LIMIT, env = "RBX_RATE_LIMIT")]
+    rate_limit: u32,
+
+    /// Seconds to wait for Studio to reply to a tool call before failing it
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = DEFAULT_TOOL_TIMEOUT_SECS,
+        env = "RBX_TOOL_TIMEOUT"
+    )]
+    tool_timeout: u64,
+
+    /// Directory that relative paths given to file tools are resolved against [default: current directory]
+    #[arg(long, value_name = "PATH", env = "RBX_WORKDIR")]
+    workdir: Option<PathBuf>,
+
+    /// Reject file tool paths that resolve outside of the working directory
+    #[arg(long, env = "RBX_SANDBOX_FILES")]
+    sandbox_files: bool,
+
+    /// Only log the size and first line of run_code scripts in debug logs
+    #[arg(long, env = "RBX_REDACT_LOGS")]
+    redact_logs: bool,
+
+    /// Truncate tool replies longer than this many bytes, 0 disables the limit
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_RESPONSE_BYTES,
+        env = "RBX_MAX_RESPONSE_BYTES"
+    )]
+    max_response_bytes: usize,
+
+    /// Let identical run_code calls made within this many milliseconds share one execution instead
+    /// of running the script twice, 0 disables. Only use this if repeated scripts are safe to skip
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 0,
+        env = "RBX_DEDUP_RUN_CODE"
+    )]
+    dedup_run_code: u64,
+
+    /// Journal queued commands to this file and replay the ones left unfinished by a previous run.
+    /// Unfinished commands that may change the place, such as run_code, are logged and dropped
+    /// instead of being run again
+    #[arg(long, value_name = "PATH", env = "RBX_PERSIST")]
+    persist: Option<PathBuf>,
+
+    /// Only offer these tools to the MCP client, comma separated (e.g. get_project_structure,ping)
+    #[arg(
+        long,
+        value_name = "TOOLS",
+        value_delimiter = ',',
+        env = "RBX_ENABLE_TOOLS"
+    )]
+    enable_tools: Option<Vec<String>>,
+
+    /// Never offer these tools to the MCP client, comma separated (e.g. run_code)
+    #[arg(
+        long,
+        value_name = "TOOLS",
+        value_delimiter = ',',
+        env = "RBX_DISABLE_TOOLS"
+    )]
+    disable_tools: Vec<String>,
+
+    /// Refuse tools that write data living outside the place, such as data_store_set
+    #[arg(long, env = "RBX_READ_ONLY")]
+    read_only: bool,
+
+    /// Fail tool calls right away with a "Studio not connected" error when the plugin hasn't
+    /// polled recently, instead of waiting for Studio to show up
+    #[arg(long, env = "RBX_NO_WAIT")]
+    no_wait: bool,
+
+    /// Let tools that change the place, such as run_code, run while Studio is running it in Run
+    /// mode instead of refusing them. Their changes are discarded when Run mode stops
+    #[arg(long, env = "RBX_ALLOW_EDITS_WHILE_RUNNING")]
+    allow_edits_while_running: bool,
+
+    /// When installing, don't wait for the user with a pause or dialog and exit with an error
+    /// status if the install failed
+    #[arg(long, conflicts_with = "stdio", env = "RBX_NON_INTERACTIVE")]
+    non_interactive: bool,
+
+    /// Answer tool calls with canned replies from a built-in fake plugin instead of Studio
+    #[arg(long, env = "RBX_MOCK")]
+    mock: bool,
+
+    /// OTLP/HTTP endpoint to export tracing spans to, e.g. http://localhost:4318/v1/traces
+    #[cfg(feature = "otlp")]
+    #[arg(long, value_name = "URL", env = "RBX_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_writer(io::stderr)
-        .with_target(false)
-        .with_thread_ids(true)
-        .init();
-
     let args = Args::parse();
+
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .with_writer(io::stderr)
+            .with_target(false)
+            .with_thread_ids(true)
+            .with_filter(EnvFilter::from_default_env()),
+    );
+    #[cfg(feature = "otlp")]
+    let (subscriber, tracer_provider) = match &args.otlp_endpoint {
+        Some(endpoint) => {
+            let (layer, provider) = telemetry::otlp_layer(endpoint)?;
+            (subscriber.with(Some(layer)), Some(provider))
+        }
+        None => (subscriber.with(None), None),
+    };
+    subscriber.init();
+
     if !args.stdio {
-        return install::install().await;
+        return install::install(args.non_interactive).await;
     }
 
     tracing::debug!("Debug MCP tracing enabled");
 
-    let server_state = Arc::new(Mutex::new(AppState::new()));
+    let workdir = match args.workdir {
+        Some(workdir) => workdir,
+        None => std::env::current_dir()?,
+    };
+    let workdir = std::fs::canonicalize(&workdir)
+        .wrap_err_with(|| format!("Invalid working directory {}", workdir.display()))?;
+    let mut config = ServerConfig {
+        server_name: args.server_name,
+        rate_limit: args.rate_limit,
+        tool_timeout: std::time::Duration::from_secs(args.tool_timeout),
+        workdir,
+        sandbox_files: args.sandbox_files,
+        max_response_bytes: args.max_response_bytes,
+        read_only: args.read_only,
+        run_code_dedup_window: std::time::Duration::from_millis(args.dedup_run_code),
+        enabled_tools: args.enable_tools,
+        disabled_tools: args.disable_tools,
+        no_wait: args.no_wait,
+        allow_edits_while_running: args.allow_edits_while_running,
+        redact_logs: args.redact_logs,
+        ..Default::default()
+    };
+    if let Some(instructions) = args.instructions {
+        config.instructions = instructions;
+    } else if let Some(path) = args.instructions_file {
+        config.instructions = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Could not read instructions from {}", path.display()))?;
+    }
+
+    let tool_timeout = config.tool_timeout;
+    let no_wait = config.no_wait;
+    let server_state = Arc::new(AppState::new());
+    if let Some(path) = &args.persist {
+        let (replayed, dropped) = server_state.open_journal(path, config.redact_logs).await?;
+        tracing::info!(
+            "Journaling commands to {}, replayed {replayed}, dropped {dropped} that may change the place",
+            path.display()
+        );
+    }
+
+    // Built before binding the port so a bad tool list fails before anything is started.
+    let server = RBXStudioServer::new(Arc::clone(&server_state), config)?;
 
     let (close_tx, close_rx) = tokio::sync::oneshot::channel();
 
@@ -47,13 +224,14 @@ async fn main() -> Result<()> {
         tokio::net::TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), STUDIO_PLUGIN_PORT)).await;
 
     let server_state_clone = Arc::clone(&server_state);
+    let plugin_url = format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}");
+    let mut mock_task = None;
     let server_handle = if let Ok(listener) = listener {
-        let app = axum::Router::new()
-            .route("/request", get(request_handler))
-            .route("/response", post(response_handler))
-            .route("/proxy", post(proxy_handler))
-            .with_state(server_state_clone);
+        let app = plugin_router(server_state_clone);
         tracing::info!("This MCP instance is HTTP server listening on {STUDIO_PLUGIN_PORT}");
+        if args.mock {
+            mock_task = Some(tokio::spawn(mock_plugin::run(plugin_url)));
+        }
         tokio::spawn(async {
             axum::serve(listener, app)
                 .with_graceful_shutdown(async move {
@@ -63,24 +241,80 @@ async fn main() -> Result<()> {
                 .unwrap();
         })
     } else {
+        probe_primary().await?;
+        server_state.set_proxying();
         tracing::info!("This MCP instance will use proxy since port is busy");
+        if args.mock {
+            tracing::warn!("Ignoring --mock, the instance owning the port talks to the plugin");
+        }
         tokio::spawn(async move {
-            dud_proxy_loop(server_state_clone, close_rx).await;
+            dud_proxy_loop(
+                server_state_clone,
+                close_rx,
+                plugin_url,
+                tool_timeout,
+                no_wait,
+            )
+            .await;
         })
     };
 
-    // Create an instance of our counter router
-    let service = RBXStudioServer::new(Arc::clone(&server_state))
+    let service = server
         .serve(rmcp::transport::stdio())
         .await
         .inspect_err(|e| {
             tracing::error!("serving error: {:?}", e);
         })?;
-    service.waiting().await?;
 
+    let cancel = service.cancellation_token();
+    let shutdown_state = Arc::clone(&server_state);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::info!("Received Ctrl-C, draining in-flight commands");
+            drain_in_flight(shutdown_state).await;
+            cancel.cancel();
+        }
+    });
+    #[cfg(unix)]
+    tokio::spawn(watch_parent_exit(service.cancellation_token()));
+    let reason = service.waiting().await?;
+    tracing::info!("MCP service stopped: {reason:?}");
+
+    server_state.set_shutting_down();
+    if let Some(mock_task) = mock_task {
+        mock_task.abort();
+    }
     close_tx.send(()).ok();
     tracing::info!("Waiting for web server to gracefully shutdown");
-    server_handle.await.ok();
+    // Don't let a plugin long poll that is still open keep an orphaned server holding the port.
+    if tokio::time::timeout(SHUTDOWN_GRACE_PERIOD, server_handle)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Web server didn't shut down in time");
+    }
+    #[cfg(feature = "otlp")]
+    if let Some(provider) = tracer_provider {
+        telemetry::shutdown(provider).await;
+    }
     tracing::info!("Bye!");
     Ok(())
 }
+
+#[cfg(unix)]
+const PARENT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Stops the server when the process that spawned it goes away. Stdin usually reaches EOF then,
+/// but not if the pipe was inherited by another process, so the parent id is checked as well.
+#[cfg(unix)]
+async fn watch_parent_exit(cancel: rmcp::service::RunningServiceCancellationToken) {
+    let parent = std::os::unix::process::parent_id();
+    loop {
+        tokio::time::sleep(PARENT_POLL_INTERVAL).await;
+        if std::os::unix::process::parent_id() != parent {
+            tracing::info!("Parent process {parent} exited, shutting down");
+            cancel.cancel();
+            return;
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use crate::rbx_studio_server::{HeartbeatResponse, HEARTBEAT_INTERVAL, STUDIO_VERSION_HEADER};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::Duration;
+
+/// Version reported to `/health` and `get_studio_version` while mocking.
+const MOCK_STUDIO_VERSION: &str = "mock";
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Stands in for the Studio plugin: long polls `/request` of the server at `base` like the real
+/// plugin does and answers every command with a canned but plausibly shaped reply. Meant for
+/// developing and testing the server without Roblox Studio.
+pub async fn run(base: String) {
+    let client = reqwest::Client::new();
+    tracing::info!("Mock plugin polling {base}");
+    tokio::join!(poll(&client, &base), heartbeat(&client, &base));
+}
+
+async fn heartbeat(client: &reqwest::Client, base: &str) {
+    let mut interval = HEARTBEAT_INTERVAL;
+    loop {
+        let res = client.post(format!("{base}/heartbeat")).send().await;
+        match res {
+            Ok(res) => {
+                if let Ok(reply) = res.json::<HeartbeatResponse>().await {
+                    interval = Duration::from_millis(reply.interval_ms);
+                }
+            }
+            Err(e) => tracing::warn!("Mock plugin failed to send a heartbeat: {e}"),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn poll(client: &reqwest::Client, base: &str) {
+    // Labels of the undo waypoints the plugin would have committed, one for every command that
+    // may change the place.
+    let mut waypoints = Vec::new();
+    loop {
+        let res = client
+            .get(format!("{base}/request"))
+            .header(STUDIO_VERSION_HEADER, MOCK_STUDIO_VERSION)
+            .send()
+            .await;
+        let request = match res {
+            Ok(res) if res.status().is_success() => res.json::<Value>().await,
+            // 423 means the long poll timed out without a command.
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!("Mock plugin failed to poll: {e}");
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::error!("Mock plugin got an invalid request: {e}");
+                continue;
+            }
+        };
+        let expired = request["deadline_ms"]
+            .as_u64()
+            .is_some_and(|deadline| deadline < now_ms());
+        if !expired && request["changes_place"] != false {
+            if let Some((variant, _)) = request["args"]
+                .as_object()
+                .and_then(|args| args.iter().next())
+            {
+                waypoints.push(format!("MCP: {variant}"));
+            }
+        }
+        let mut response = match canned_reply(&request["args"], &waypoints) {
+            _ if expired => json!({
+                "response": "Skipped, the deadline passed before Studio got to the command",
+                "is_error": true,
+                "error_code": "timeout",
+            }),
+            Some(reply) => json!({ "response": reply }),
+            None => json!({
+                "response": "No tool found to handle request",
+                "is_error": true,
+                "error_code": "unknown_variant",
+            }),
+        };
+        response["id"] = request["id"].clone();
+        if let Err(e) = client
+            .post(format!("{base}/response"))
+            .json(&response)
+            .send()
+            .await
+        {
+            tracing::error!("Mock plugin failed to respond: {e}");
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Reply the plugin would plausibly give for a command, `None` for unknown variants.
+fn canned_reply(args: &Value, waypoints: &[String]) -> Option<String> {
+    let (variant, args) = args.as_object()?.iter().next()?;
+    let path = |field: &str| args[field].as_str().unwrap_or("Workspace").to_string();
+    let vector = json!({ "x": 0.0, "y": 5.0, "z": 0.0 });
+    let reply = match variant.as_str() {
+        "RunCode" => return Some("[OUTPUT] mock run_code output\n".to_string()),
+        "InsertModel" => return Some(format!("{}.MockModel", path("parent_path"))),
+        "SearchMarketplace" => json!({
+            "query": args["query"],
+            "total": 1,
+            "assets": [{
+                "name": "Mock Model",
+                "asset_id": 12345678,
+                "asset_version_id": 87654321,
+                "creator": "Roblox",
+            }],
+        }),
+        "InsertModelByAssetId" => return Some(format!("{}.MockModel", path("parent_path"))),
+        "DeletePart" => return Some(format!("Deleted {}", path("part_name"))),
+        "GetProjectStructure" => json!({
+            "name": "game",
+            "className": "DataModel",
+            "children": [{ "name": "Workspace", "className": "Workspace", "children": [] }],
+        }),
+        "ReplaceInScripts" => {
+            json!({ "dry_run": args["dry_run"] == true, "total": 0, "scripts": [], "failed": [] })
+        }
+        "ShowMessage" => return Some("Message shown".to_string()),
+        "FocusInstance" => json!({ "position": vector, "target": vector, "distance": 10.0 }),
+        "GetCamera" | "SetCamera" => json!({
+            "position": vector,
+            "look_vector": { "x": 0.0, "y": 0.0, "z": -1.0 },
+            "cframe": [0, 5, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1],
+            "field_of_view": 70,
+        }),
+        "InsertLocalModel" => json!({ "inserted": [format!("{}.MockModel", path("parent_path"))] }),
+        "ExportModel" => return Some(BASE64.encode(b"mock rbxm")),
+        "Ping" => return Some("pong".to_string()),
+        "ListServices" => json!({ "loaded": ["Workspace", "Lighting"], "available": [] }),
+        "GetStudioVersion" => return Some(MOCK_STUDIO_VERSION.to_string()),
+        "GetPhysicsSettings" | "SetPhysicsSettings" => json!({
+            "gravity": 196.2,
+            "air_density": 0.0012,
+            "global_wind": { "x": 0.0, "y": 0.0, "z": 0.0 },
+            "fallen_parts_destroy_height": -500,
+        }),
+        "CountDescendants" => json!({ "count": 0, "children": 0, "capped": false }),
+        "WaitFor" => json!({ "satisfied": true, "elapsed_ms": 0 }),
+        "HighlightInstances" => json!({ "highlights": [] }),
+        "ClearHighlights" => json!({ "removed": 0 }),
+        "ResolvePath" => json!({
+            "matches": [{ "path": path("path"), "class_name": "Part", "confidence": "exact" }],
+            "found": true,
+        }),
+        "SetProperties" => {
+            let applied: Vec<&String> = args["properties"]
+                .as_object()
+                .map(|properties| properties.keys().collect())
+                .unwrap_or_default();
+            json!({ "applied": applied, "failed": [], "success": true })
+        }
+        "SetSelectionProperty" => json!({ "selected": 1, "changed": 1, "failed": [] }),
+        "GetClassMembers" => json!({
+            "class_name": args["class_name"],
+            "properties": [{ "Name": "Name" }],
+            "methods": [{ "Name": "Destroy" }],
+            "events": [{ "Name": "Changed" }],
+        }),
+        "GetPlaceConfig" | "SetPlaceConfig" => json!({
+            "streaming_enabled": false,
+            "streaming_min_radius": 64,
+            "streaming_target_radius": 1024,
+            "lighting_technology": "Future",
+            "respect_filtering_enabled": true,
+        }),
+        "KvSet" => json!({
+            "key": args["key"],
+            "found": true,
+            "value": args["value"],
+            "type": "string",
+        }),
+        "KvGet" => json!({ "key": args["key"], "found": false }),
+        "KvList" => json!({ "entries": [] }),
+        "DataStoreGet" => json!({
+            "store_name": args["store_name"],
+            "key": args["key"],
+            "found": false,
+        }),
+        "DataStoreList" => json!({ "data_stores": [], "truncated": false }),
+        "DataStoreSet" => json!({
+            "store_name": args["store_name"],
+            "key": args["key"],
+            "version": "mock",
+        }),
+        "ListActions" => json!({
+            "actions": [{
+                "id": "group_selection",
+                "text": "Group Selection",
+                "description": "Groups the selected instances into a new Model and selects it",
+            }],
+        }),
+        "TriggerAction" => json!({
+            "action_id": args["action_id"],
+            "found": args["action_id"] == "group_selection",
+            "fired": args["action_id"] == "group_selection",
+        }),
+        "SimulateInput" => json!({ "action": args["action"], "success": true }),
+        "GetEditMode" => json!({ "mode": "Select" }),
+        "SetEditMode" => json!({ "mode": args["mode"] }),
+        "CreateCollisionGroup" | "SetCollisionGroupCollidable" => json!({
+            "groups": [{ "name": "Default", "collides_with": ["Default"] }],
+        }),
+        "SetCollisionGroup" => json!({
+            "group": args["group"],
+            "changed": 1,
+            "members": [path("instance_path")],
+            "truncated": false,
+        }),
+        "ApplyMaterial" => json!({
+            "dry_run": args["dry_run"] == true,
+            "matched": 0,
+            "changed": 0,
+            "parts": [],
+            "truncated": false,
+        }),
+        "GetDirtyState" => json!({
+            "modified": !waypoints.is_empty(),
+            "modification_count": waypoints.len(),
+            "can_undo": false,
+            "tracked_since": "plugin_load",
+        }),
+        "RequireModule" => json!({
+            "instance_path": path("instance_path"),
+            "cached": false,
+            "type": "table",
+            "keys": [{ "name": "new", "type": "function" }],
+            "truncated": false,
+        }),
+        "CreateGui" => json!({
+            "path": format!(
+                "{}.{}",
+                args["parent_path"].as_str().unwrap_or("StarterGui"),
+                args["spec"]["name"]
+                    .as_str()
+                    .or(args["spec"]["class_name"].as_str())
+                    .unwrap_or("ScreenGui")
+            ),
+            "created": 1,
+        }),
+        "FindDuplicates" => json!({
+            "dry_run": args["dry_run"] != false,
+            "sets": [],
+            "removed": 0,
+            "truncated": false,
+        }),
+        "RemoveEmpty" => json!({
+            "dry_run": args["dry_run"] != false,
+            "found": 0,
+            "removed": 0,
+            "paths": [],
+            "truncated": false,
+        }),
+        "GetSessionInfo" => json!({
+            "team_create": false,
+            "place_id": 0,
+            "game_id": 0,
+            "creator_id": 0,
+            "creator_type": "User",
+        }),
+        "CreateGrid" => {
+            let counts = &args["counts"];
+            let count: u64 = ["x", "y", "z"]
+                .iter()
+                .map(|axis| counts[axis].as_u64().unwrap_or(1))
+                .product();
+            json!({
+                "path": format!(
+                    "{}.{}",
+                    path("parent_path"),
+                    args["name"].as_str().unwrap_or("Grid")
+                ),
+                "count": count,
+            })
+        }
+        "SetScriptEnabled" => json!({
+            "enabled": args["enabled"],
+            "matched": 1,
+            "changed": 1,
+            "paths": [path("instance_path")],
+            "truncated": false,
+        }),
+        "GetDiagnosticsSummary" => json!({
+            "errors": 0,
+            "warnings": 0,
+            "latest_timestamp": args["since"].as_f64().unwrap_or(0.0),
+        }),
+        "SetAppearance" => json!({
+            "path": path("instance_path"),
+            "transparency": args["transparency"].as_f64().unwrap_or(0.0),
+            "reflectance": args["reflectance"].as_f64().unwrap_or(0.0),
+            "material": args["material"].as_str().unwrap_or("Plastic"),
+            "color": args.get("color").cloned().unwrap_or(json!({ "r": 0.64, "g": 0.64, "b": 0.64 })),
+            "cast_shadow": args["cast_shadow"].as_bool().unwrap_or(true),
+        }),
+        "SnapshotProperties" => json!({
+            "name": args["name"],
+            "instances": args["instance_paths"].as_array().map_or(0, Vec::len),
+            "properties_recorded": 0,
+            "expires_at": 0,
+        }),
+        "RestoreSnapshot" => json!({
+            "name": args["name"],
+            "changed": 0,
+            "changes": [],
+            "truncated": false,
+            "missing": [],
+            "failed": [],
+        }),
+        "ListInstalledPlugins" => json!({
+            "mcp_plugin": { "loaded": true, "name": "MCPStudioPlugin" },
+            "enumerable": false,
+            "widgets": [],
+        }),
+        "WorldToScreen" => {
+            let points: Vec<Value> = args["points"]
+                .as_array()
+                .map(|points| {
+                    points
+                        .iter()
+                        .map(|_| {
+                            json!({
+                                "x": 400.0,
+                                "y": 300.0,
+                                "depth": 10.0,
+                                "on_screen": true,
+                                "behind_camera": false,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            json!({ "viewport_size": { "x": 800.0, "y": 600.0 }, "points": points })
+        }
+        "ScreenToWorld" => json!({
+            "origin": vector,
+            "direction": { "x": 0.0, "y": 0.0, "z": -1.0 },
+        }),
+        "GetVelocity" | "SetVelocity" | "ApplyImpulse" => {
+            let velocity = |field: &str| match &args[field] {
+                Value::Null => json!({ "x": 0.0, "y": 0.0, "z": 0.0 }),
+                velocity => velocity.clone(),
+            };
+            json!({
+                "assembly_root": path("instance_path"),
+                "anchored": false,
+                "mass": 1.0,
+                "linear_velocity": velocity("linear"),
+                "angular_velocity": velocity("angular"),
+            })
+        }
+        "GetOpenDocuments" => json!({ "documents": [] }),
+        "RunTest" => {
+            let results: Vec<Value> = args["assertions"]
+                .as_array()
+                .map(|assertions| {
+                    assertions
+                        .iter()
+                        .enumerate()
+                        .map(|(index, assertion)| {
+                            json!({
+                                "name": assertion["name"]
+                                    .as_str()
+                                    .map_or_else(|| format!("assertion {}", index + 1), String::from),
+                                "passed": true,
+                                "duration_ms": 0,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            json!({
+                "passed": true,
+                "total": results.len(),
+                "failed": 0,
+                "skipped": 0,
+                "results": results,
+            })
+        }
+        "GetPrimaryPart" => json!({ "model": path("model_path"), "set": false }),
+        "SetPrimaryPart" => json!({
+            "model": path("model_path"),
+            "primary_part": path("part_path"),
+            "set": true,
+        }),
+        "GetPerformanceStats" => json!({
+            "instance_count": 1000,
+            "primitives_count": 10,
+            "moving_primitives_count": 0,
+            "contacts_count": 0,
+            "heartbeat_time_ms": 1.0,
+            "physics_step_time_ms": 0.5,
+            "total_memory_mb": 512.0,
+            "memory_mb": { "Instances": 16.0 },
+        }),
+        "CreateParticleEmitter" | "CreateBeam" | "CreateTrail" => {
+            let class_name = variant.trim_start_matches("Create");
+            json!({
+                "path": format!(
+                    "{}.{}",
+                    path("parent_path"),
+                    args["name"].as_str().unwrap_or(class_name)
+                ),
+                "class_name": class_name,
+                "set": [],
+            })
+        }
+        "GetWorkspaceFlags" | "SetWorkspaceFlags" => json!({
+            "streaming_enabled": false,
+            "streaming_min_radius": 64,
+            "streaming_target_radius": 1024,
+            "streaming_integrity_mode": "Default",
+            "physics_stepping_method": "Default",
+            "touches_use_collision_groups": false,
+        }),
+        "ListMyAssets" => json!({
+            "user_id": 1,
+            "assets": [{
+                "name": "My Model",
+                "asset_id": 23456789,
+                "asset_version_id": 98765432,
+                "set": "My Models",
+            }],
+        }),
+        "Measure" => {
+            let located = |index: usize| {
+                json!({
+                    "path": args["instance_paths"][index].as_str().unwrap_or("Workspace"),
+                    "pivot": vector,
+                    "center": vector,
+                })
+            };
+            json!({
+                "a": located(0),
+                "b": located(1),
+                "offset": { "x": 0.0, "y": 0.0, "z": 0.0 },
+                "distance": 0.0,
+                "center_distance": 0.0,
+                "relationship": "unrelated",
+            })
+        }
+        "SetStudioSettings" => json!({
+            "grid_size": args["grid_size"].as_f64().unwrap_or(1.0),
+            "rotate_increment": args["rotate_increment"].as_f64().unwrap_or(15.0),
+        }),
+        "SuggestName" => json!({ "name": args["base_name"] }),
+        "GetRunState" => json!({ "mode": "edit", "scripts_running": false }),
+        "GetPropertiesBulk" => {
+            let entries = args["entries"].as_array().cloned().unwrap_or_default();
+            let results: Vec<Value> = entries
+                .iter()
+                .map(|entry| json!({ "path": entry["instance_path"], "values": {} }))
+                .collect();
+            json!({ "results": results })
+        }
+        "GetHistory" => {
+            let waypoints: Vec<Value> = waypoints
+                .iter()
+                .rev()
+                .map(|label| json!({ "label": label, "timestamp": 0 }))
+                .collect();
+            json!({ "waypoints": waypoints })
+        }
+        "WeldParts" => {
+            json!({ "path": "Workspace.Part.WeldConstraint", "class_name": "WeldConstraint" })
+        }
+        "GetBoundingBox" => json!({
+            "oriented": { "cframe": [0, 5, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1], "size": vector },
+            "axis_aligned": { "center": vector, "size": vector, "min": vector, "max": vector },
+            "part_count": 1,
+        }),
+        "DiffInstances" => json!({
+            "differences": [],
+            "only_in_a": [],
+            "only_in_b": [],
+            "truncated": false,
+        }),
+        _ => return None,
+    };
+    Some(reply.to_string())
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use tokio::time::Instant;
+
+/// Classic token bucket: holds up to `capacity` tokens and refills `rate` tokens per second.
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket rate limiter keyed by `K`, so each tool gets its own budget.
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    rate: f64,
+    capacity: f64,
+    buckets: HashMap<K, TokenBucket>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    /// Allows `per_second` calls per second per key, with bursts of the same size. A rate of zero
+    /// disables limiting.
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            rate: per_second as f64,
+            capacity: per_second as f64,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token for `key`, returning false if its bucket is empty.
+    pub fn try_acquire(&mut self, key: K) -> bool {
+        if self.rate == 0.0 {
+            return true;
+        }
+        let now = Instant::now();
+        let bucket = self.buckets.entry(key).or_insert(TokenBucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn bursts_up_to_the_rate_then_refills() {
+        let mut limiter = RateLimiter::new(2);
+        assert!(limiter.try_acquire("run_code"));
+        assert!(limiter.try_acquire("run_code"));
+        assert!(!limiter.try_acquire("run_code"));
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert!(limiter.try_acquire("run_code"));
+        assert!(!limiter.try_acquire("run_code"));
+
+        // Refilling stops at the capacity however long the bucket sat idle.
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(limiter.try_acquire("run_code"));
+        assert!(limiter.try_acquire("run_code"));
+        assert!(!limiter.try_acquire("run_code"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keys_have_separate_buckets() {
+        let mut limiter = RateLimiter::new(1);
+        assert!(limiter.try_acquire("run_code"));
+        assert!(!limiter.try_acquire("run_code"));
+        assert!(limiter.try_acquire("get_camera"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_disables_limiting() {
+        let mut limiter = RateLimiter::new(0);
+        assert!((0..100).all(|_| limiter.try_acquire("run_code")));
+    }
+}
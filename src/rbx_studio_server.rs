@@ -1,298 +1,4606 @@
-use crate::error::Result;
-use axum::http::StatusCode;
+use crate::error::{Result, ToolError, ToolErrorCode};
+use crate::journal::Journal;
+use crate::rate_limit::RateLimiter;
+use crate::roblox_types::{
+    ClassName, LightingTechnology, Material, PhysicsSteppingMethod, RibbonTool,
+    StreamingIntegrityMode,
+};
+use crate::schema_summary::describe_arguments;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use axum::{extract::State, Json};
-use color_eyre::eyre::{Error, OptionExt};
+use axum::{
+    extract::{rejection::JsonRejection, DefaultBodyLimit, Query, State},
+    routing::{get, post},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use color_eyre::eyre::Error;
 use rmcp::{
-    handler::server::tool::Parameters,
+    handler::server::tool::{Parameters, ToolCallContext},
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, ErrorCode, Implementation, ListToolsResult,
+        LoggingLevel, LoggingMessageNotificationParam, PaginatedRequestParam,
+        ProgressNotificationParam, ProgressToken, ProtocolVersion, ServerCapabilities, ServerInfo,
+        SetLevelRequestParam,
     },
-    schemars, tool, tool_handler, tool_router, ErrorData, ServerHandler,
+    schemars,
+    service::RequestContext,
+    tool, tool_router, ErrorData, Peer, RoleServer, ServerHandler,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::future::Future;
+use std::mem::Discriminant;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::oneshot::Receiver;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot::{self, Receiver};
 use tokio::sync::{mpsc, watch, Mutex};
-use tokio::time::Duration;
+use tokio::time::{Duration, Instant};
+use tracing::Instrument;
 use uuid::Uuid;
 
 pub const STUDIO_PLUGIN_PORT: u16 = 44755;
+/// Header the plugin sets on every poll so the server knows which Studio build is connected.
+pub const STUDIO_VERSION_HEADER: &str = "x-studio-version";
+/// Upper bound for request bodies posted by the plugin, large enough for exported models.
+const MAX_PLUGIN_BODY_BYTES: usize = 64 * 1024 * 1024;
 const LONG_POLL_DURATION: Duration = Duration::from_secs(15);
+/// How often the plugin posts to `/heartbeat`. The plugin learns the interval from the reply, so
+/// this is the only place it is defined.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// The plugin counts as disconnected once three heartbeats in a row were missed.
+const HEARTBEAT_WINDOW: Duration = Duration::from_secs(HEARTBEAT_INTERVAL.as_secs() * 3);
+/// Attempts made to reach the primary instance before a proxied command is failed. Only
+/// connection failures are retried since the command can't have run yet.
+const PROXY_CONNECT_ATTEMPTS: u32 = 3;
+const PROXY_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// How long the instance holding the plugin port gets to answer `/health` before it is assumed
+/// to be some other application.
+const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+pub const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_LOCAL_MODEL_BYTES: u64 = 16 * 1024 * 1024;
+/// Commands waiting for the plugin beyond this are rejected instead of piling up unseen.
+const MAX_QUEUED_COMMANDS: usize = 64;
+/// Default time to wait for Studio to reply to a tool call.
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 300;
+/// Default cap on the size of a tool reply passed to the MCP client.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+/// Longest wait_for allowed, longer waits should be split so the model stays responsive.
+const MAX_WAIT_FOR_MS: u64 = 10 * 60 * 1000;
+/// Longest collision group name PhysicsService accepts.
+const MAX_COLLISION_GROUP_NAME_CHARS: usize = 100;
+/// Extra time given to `wait_for` on top of its own timeout so the plugin can report back.
+const WAIT_FOR_REPLY_MARGIN: Duration = Duration::from_secs(5);
+pub const DEFAULT_SERVER_NAME: &str = "Roblox Studio MCP";
+/// Default number of calls per second allowed for each tool.
+pub const DEFAULT_RATE_LIMIT: u32 = 10;
+/// Longest prefix of a redacted script's first line that is still logged.
+const REDACTED_PREVIEW_CHARS: usize = 80;
+
+const DEFAULT_INSTRUCTIONS: &str = "Use run_code to run Luau in Roblox Studio to query or change the open place. \
+Dedicated tools such as get_project_structure, insert_model and replace_in_scripts cover common tasks \
+and should be preferred when they fit.";
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct ToolArguments {
     args: ToolArgumentValues,
     id: Option<Uuid>,
+    /// Unix time in milliseconds after which the caller no longer waits for the reply, the plugin
+    /// skips commands it only gets to later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deadline_ms: Option<u64>,
+    /// Tells the plugin to refuse the command while Studio is running the place, set on tools
+    /// that change it unless the server runs with `--allow-edits-while-running`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    refuse_while_running: bool,
+    /// Whether the command may change the place. The plugin only records an undo waypoint for
+    /// commands that may, otherwise every call would show up as an unsaved change.
+    #[serde(default = "changes_place_by_default")]
+    changes_place: bool,
+}
+
+/// Commands from before `changes_place` was sent are assumed to change the place.
+fn changes_place_by_default() -> bool {
+    true
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct RunCommandResponse {
     response: String,
     id: Uuid,
+    #[serde(default)]
+    is_error: bool,
+    /// Set on intermediate chunks, the reply is complete once a chunk without it arrives.
+    #[serde(default)]
+    partial: bool,
+    /// Kind of failure when `is_error` is set, plugin errors are assumed when it is missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_code: Option<ToolErrorCode>,
+    /// When set, `response` is base64 encoded content of this type rather than plain text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+}
+
+impl RunCommandResponse {
+    fn into_result(self) -> ToolResult {
+        if self.is_error {
+            Err(ToolError::new(
+                self.error_code.unwrap_or(ToolErrorCode::PluginError),
+                self.response,
+            ))
+        } else if let Some(content_type) = self.content_type {
+            Ok(ToolOutput::Binary {
+                content_type,
+                data: self.response,
+            })
+        } else {
+            Ok(ToolOutput::Text(self.response))
+        }
+    }
+
+    fn from_result(id: Uuid, result: ToolResult) -> Self {
+        let (response, error_code, content_type) = match result {
+            Ok(ToolOutput::Text(text)) => (text, None, None),
+            Ok(ToolOutput::Binary { content_type, data }) => (data, None, Some(content_type)),
+            Err(err) => (err.message, Some(err.code), None),
+        };
+        Self {
+            response,
+            id,
+            is_error: error_code.is_some(),
+            partial: false,
+            error_code,
+            content_type,
+        }
+    }
+}
+
+/// Successful reply of a command. Most tools reply with text, binary content such as images is
+/// carried as base64 along with its content type so it survives the trip through a proxy.
+#[derive(Debug, Clone)]
+pub enum ToolOutput {
+    Text(String),
+    Binary { content_type: String, data: String },
+}
+
+impl ToolOutput {
+    /// The reply as text, binary content is returned in its base64 form.
+    fn into_text(self) -> String {
+        match self {
+            ToolOutput::Text(text) => text,
+            ToolOutput::Binary { data, .. } => data,
+        }
+    }
+
+    fn into_content(self) -> Content {
+        match self {
+            ToolOutput::Text(text) => Content::text(text),
+            ToolOutput::Binary { content_type, data } if content_type.starts_with("image/") => {
+                Content::image(data, content_type)
+            }
+            ToolOutput::Binary { content_type, data } => Content::text(
+                serde_json::json!({ "content_type": content_type, "base64": data }).to_string(),
+            ),
+        }
+    }
+}
+
+type ToolResult = Result<ToolOutput, ToolError>;
+
+/// Message delivered to the caller waiting on a command. The plugin may stream any number of
+/// chunks of output before the final reply.
+#[derive(Debug)]
+enum CommandReply {
+    Chunk(String),
+    /// Studio log lines posted to `/log` while the command runs, they aren't part of the reply.
+    Log(Vec<LogLine>),
+    Done(ToolResult),
+}
+
+/// Severity of a Studio log line, as reported by `LogService.MessageOut`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StudioLogLevel {
+    Output,
+    Info,
+    Warning,
+    Error,
+}
+
+impl StudioLogLevel {
+    fn mcp_level(self) -> LoggingLevel {
+        match self {
+            StudioLogLevel::Output | StudioLogLevel::Info => LoggingLevel::Info,
+            StudioLogLevel::Warning => LoggingLevel::Warning,
+            StudioLogLevel::Error => LoggingLevel::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LogLine {
+    level: StudioLogLevel,
+    message: String,
+}
+
+/// Forwards the Studio log lines of a command to the MCP client as logging notifications.
+struct LogForwarder {
+    peer: Peer<RoleServer>,
+    /// Least severe level the client asked for with `logging/setLevel`.
+    min_level: LoggingLevel,
+}
+
+impl LogForwarder {
+    async fn forward(&self, lines: Vec<LogLine>) {
+        for line in lines {
+            let level = line.level.mcp_level();
+            // The levels are declared from least to most severe.
+            if (level as u8) < (self.min_level as u8) {
+                continue;
+            }
+            let result = self
+                .peer
+                .notify_logging_message(LoggingMessageNotificationParam {
+                    level,
+                    logger: Some("studio".to_string()),
+                    data: serde_json::Value::String(line.message),
+                })
+                .await;
+            if let Err(e) = result {
+                tracing::debug!("Failed to send logging notification: {e}");
+            }
+        }
+    }
+}
+
+/// Waits for the final reply of a command, prepending any streamed chunks to it. Chunks are
+/// dropped if the final reply turns out to be binary. Log lines are forwarded as they arrive when
+/// there is somewhere to forward them to and dropped otherwise.
+async fn collect_reply(
+    rx: &mut mpsc::UnboundedReceiver<CommandReply>,
+    logs: Option<&LogForwarder>,
+) -> ToolResult {
+    let mut output = String::new();
+    loop {
+        match rx.recv().await {
+            Some(CommandReply::Chunk(chunk)) => output.push_str(&chunk),
+            Some(CommandReply::Log(lines)) => {
+                if let Some(logs) = logs {
+                    logs.forward(lines).await;
+                }
+            }
+            Some(CommandReply::Done(Ok(ToolOutput::Text(response)))) => {
+                output.push_str(&response);
+                return Ok(ToolOutput::Text(output));
+            }
+            Some(CommandReply::Done(Ok(binary))) => return Ok(binary),
+            Some(CommandReply::Done(Err(mut err))) => {
+                if !output.is_empty() {
+                    err.message = format!("{output}{}", err.message);
+                }
+                return Err(err);
+            }
+            None => return Err(ToolError::internal("Couldn't receive response")),
+        }
+    }
+}
+
+/// A run_code execution that identical calls arriving shortly after it wait on instead of
+/// running the script again.
+struct InFlightRunCode {
+    started: Instant,
+    result: watch::Receiver<Option<Result<CallToolResult, ErrorData>>>,
 }
 
+/// Shared state of the server. It is split into independently locked parts so that tool calls,
+/// plugin polls and replies only contend on what they touch. No two of the locks are ever held at
+/// the same time.
 pub struct AppState {
-    process_queue: VecDeque<ToolArguments>,
-    output_map: HashMap<Uuid, mpsc::UnboundedSender<Result<String>>>,
+    queue: Mutex<CommandQueue>,
+    output_map: Mutex<HashMap<Uuid, mpsc::UnboundedSender<CommandReply>>>,
     waiter: watch::Receiver<()>,
     trigger: watch::Sender<()>,
+    shutting_down: AtomicBool,
+    studio_version: Mutex<Option<String>>,
+    /// run_code executions that can be shared, keyed by script, when deduplication is enabled.
+    run_code_in_flight: Mutex<HashMap<String, InFlightRunCode>>,
+    /// Log of queued commands, kept when the server runs with `--persist`.
+    journal: Mutex<Option<Journal>>,
+    /// When the plugin last posted to `/heartbeat`.
+    last_heartbeat: Mutex<Option<Instant>>,
+    /// Commands are forwarded to another instance, which is the one the plugin talks to.
+    proxying: AtomicBool,
+}
+pub type PackedState = Arc<AppState>;
+
+/// Commands waiting for the plugin to pick them up.
+#[derive(Default)]
+struct CommandQueue {
+    commands: VecDeque<ToolArguments>,
+    /// Fired when a queued command is handed to the plugin, only present for callers that
+    /// asked for progress notifications.
+    dispatch_notifiers: HashMap<Uuid, oneshot::Sender<()>>,
 }
-pub type PackedState = Arc<Mutex<AppState>>;
 
 impl AppState {
     pub fn new() -> Self {
         let (trigger, waiter) = watch::channel(());
         Self {
-            process_queue: VecDeque::new(),
-            output_map: HashMap::new(),
+            queue: Mutex::new(CommandQueue::default()),
+            output_map: Mutex::new(HashMap::new()),
             waiter,
             trigger,
+            shutting_down: AtomicBool::new(false),
+            studio_version: Mutex::new(None),
+            run_code_in_flight: Mutex::new(HashMap::new()),
+            journal: Mutex::new(None),
+            last_heartbeat: Mutex::new(None),
+            proxying: AtomicBool::new(false),
+        }
+    }
+
+    /// Starts journaling queued commands to `path` and queues the commands a previous run left
+    /// unfinished. Nobody waits for their replies anymore. Commands that may change the place
+    /// aren't replayed, running a script or deleting instances again without anyone asking
+    /// could do damage, so they are logged for the user to run again if they want and dropped.
+    /// Returns how many commands were replayed and how many were dropped.
+    pub async fn open_journal(
+        &self,
+        path: &Path,
+        redact_logs: bool,
+    ) -> color_eyre::Result<(usize, usize)> {
+        let (mut journal, unfinished) = Journal::open::<ToolArguments>(path)?;
+        let (pending, dropped): (Vec<_>, Vec<_>) = unfinished
+            .into_iter()
+            .partition(|(_, command)| !command.changes_place);
+        let logged = |command| LoggedCommand {
+            command,
+            redact: redact_logs,
+        };
+        for (id, command) in &dropped {
+            tracing::warn!(
+                "Not replaying command {id} left unfinished by the previous run, it may change the place: {:?}",
+                logged(command)
+            );
+            journal.finished(*id);
+        }
+        for (id, command) in &pending {
+            tracing::warn!(
+                "Replaying command {id} left unfinished by the previous run: {:?}",
+                logged(command)
+            );
+        }
+        let counts = (pending.len(), dropped.len());
+        *self.journal.lock().await = Some(journal);
+        // Their deadlines passed with the previous run, which would make the plugin skip them.
+        self.queue
+            .lock()
+            .await
+            .commands
+            .extend(pending.into_iter().map(|(_, command)| ToolArguments {
+                deadline_ms: None,
+                ..command
+            }));
+        self.trigger.send(()).ok();
+        Ok(counts)
+    }
+
+    async fn journal_finished(&self, id: Uuid) {
+        if let Some(journal) = &mut *self.journal.lock().await {
+            journal.finished(id);
         }
     }
+
+    pub fn set_shutting_down(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_proxying(&self) {
+        self.proxying.store(true, Ordering::Relaxed);
+    }
+
+    /// Notes that the plugin just reached the server, by heartbeat or by polling.
+    async fn plugin_seen(&self) {
+        *self.last_heartbeat.lock().await = Some(Instant::now());
+    }
+
+    /// Whether the plugin sent a heartbeat or polled within the last `HEARTBEAT_WINDOW`.
+    async fn plugin_connected(&self) -> bool {
+        self.last_heartbeat
+            .lock()
+            .await
+            .is_some_and(|heartbeat| heartbeat.elapsed() < HEARTBEAT_WINDOW)
+    }
+
+    /// Fails unless the plugin is connected, used by `--no-wait` to fail fast instead of queueing
+    /// commands nobody will pick up.
+    async fn require_plugin(&self) -> Result<(), ToolError> {
+        if self.plugin_connected().await {
+            return Ok(());
+        }
+        Err(ToolError::new(
+            ToolErrorCode::NotConnected,
+            "Studio not connected: no heartbeat from the MCP plugin recently. Open Studio and make sure the plugin is enabled",
+        ))
+    }
+
+    /// Queues a command for the plugin, `tx` receives the reply. The caller is registered before
+    /// the command becomes visible so a fast reply can't miss it.
+    async fn push_command(
+        &self,
+        command: ToolArguments,
+        tx: mpsc::UnboundedSender<CommandReply>,
+        notifier: Option<oneshot::Sender<()>>,
+    ) {
+        if let Some(id) = command.id {
+            self.output_map.lock().await.insert(id, tx);
+            if let Some(notifier) = notifier {
+                self.queue
+                    .lock()
+                    .await
+                    .dispatch_notifiers
+                    .insert(id, notifier);
+            }
+        }
+        self.queue.lock().await.commands.push_back(command);
+        self.trigger.send(()).ok();
+    }
+
+    async fn queued_commands(&self) -> usize {
+        self.queue.lock().await.commands.len()
+    }
+
+    /// Forgets everything about a command once its caller stopped waiting for it.
+    async fn remove_command(&self, id: Uuid) {
+        {
+            let mut queue = self.queue.lock().await;
+            queue.commands.retain(|command| command.id != Some(id));
+            queue.dispatch_notifiers.remove(&id);
+        }
+        self.output_map.lock().await.remove(&id);
+        self.journal_finished(id).await;
+    }
+
+    /// Takes the next queued command, along with the notifier telling its caller it has been
+    /// dispatched. Fire it with `notify_dispatched`.
+    async fn pop_command(&self) -> Option<(ToolArguments, Option<oneshot::Sender<()>>)> {
+        let mut queue = self.queue.lock().await;
+        let command = queue.commands.pop_front()?;
+        let notifier = command
+            .id
+            .and_then(|id| queue.dispatch_notifiers.remove(&id));
+        Some((command, notifier))
+    }
+
+    /// Takes the channel of the caller waiting for the final reply to `id`. Commands replayed
+    /// from the journal have no caller, their reply only marks them done. Late replies to
+    /// commands whose caller gave up find them already marked done, the journal ignores those.
+    async fn take_caller(&self, id: Uuid) -> Option<mpsc::UnboundedSender<CommandReply>> {
+        let tx = self.output_map.lock().await.remove(&id);
+        if tx.is_none() {
+            self.journal_finished(id).await;
+        }
+        tx
+    }
+
+    /// Drops every queued command and returns the channels of all callers still waiting for a
+    /// reply, to be failed with `fail_callers`.
+    async fn take_outstanding(&self) -> Vec<mpsc::UnboundedSender<CommandReply>> {
+        let dropped = {
+            let mut queue = self.queue.lock().await;
+            queue.dispatch_notifiers.clear();
+            std::mem::take(&mut queue.commands)
+        };
+        for id in dropped.iter().filter_map(|command| command.id) {
+            self.journal_finished(id).await;
+        }
+        self.output_map.lock().await.values().cloned().collect()
+    }
 }
 
-impl ToolArguments {
-    fn new(args: ToolArgumentValues) -> (Self, Uuid) {
-        Self { args, id: None }.with_id()
+fn notify_dispatched(notifier: Option<oneshot::Sender<()>>) {
+    if let Some(notifier) = notifier {
+        notifier.send(()).ok();
+    }
+}
+
+/// Fails every caller in `callers` with `err`, returning how many there were.
+fn fail_callers(callers: Vec<mpsc::UnboundedSender<CommandReply>>, err: ToolError) -> usize {
+    for tx in &callers {
+        tx.send(CommandReply::Done(Err(err.clone()))).ok();
+    }
+    callers.len()
+}
+
+/// Sends MCP progress notifications for a tool call whose client supplied a progress token.
+struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+}
+
+impl ProgressReporter {
+    fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        Some(Self {
+            peer: context.peer.clone(),
+            token: context.meta.get_progress_token()?,
+        })
+    }
+
+    async fn notify(&self, progress: u32, message: &str) {
+        let result = self
+            .peer
+            .notify_progress(ProgressNotificationParam {
+                progress_token: self.token.clone(),
+                progress,
+                total: None,
+                message: Some(message.to_string()),
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::debug!("Failed to send progress notification: {e}");
+        }
+    }
+
+    async fn report_dispatch(self, dispatched: oneshot::Receiver<()>) {
+        self.notify(0, "Waiting for Studio").await;
+        if dispatched.await.is_ok() {
+            self.notify(1, "Running in Studio").await;
+        }
+    }
+}
+
+impl ToolArgumentValues {
+    /// Whether the command may change the place, which is refused while Studio is running it.
+    /// Only tools known to leave the place alone are exempt, so new tools are treated as changing
+    /// it until they are added here. Tools writing data outside the place, such as DataStores or
+    /// Studio settings, count as leaving it alone. wait_for isn't exempt since its predicate is
+    /// arbitrary code that can change the place like run_code.
+    fn changes_place(&self) -> bool {
+        !matches!(
+            self,
+            ToolArgumentValues::GetProjectStructure(_)
+                | ToolArgumentValues::ShowMessage(_)
+                | ToolArgumentValues::GetCamera(_)
+                | ToolArgumentValues::ExportModel(_)
+                | ToolArgumentValues::Ping(_)
+                | ToolArgumentValues::ListServices(_)
+                | ToolArgumentValues::GetStudioVersion(_)
+                | ToolArgumentValues::GetPhysicsSettings(_)
+                | ToolArgumentValues::CountDescendants(_)
+                | ToolArgumentValues::HighlightInstances(_)
+                | ToolArgumentValues::ClearHighlights(_)
+                | ToolArgumentValues::ResolvePath(_)
+                | ToolArgumentValues::GetClassMembers(_)
+                | ToolArgumentValues::GetPlaceConfig(_)
+                | ToolArgumentValues::KvGet(_)
+                | ToolArgumentValues::KvList(_)
+                | ToolArgumentValues::GetHistory(_)
+                | ToolArgumentValues::GetBoundingBox(_)
+                | ToolArgumentValues::DiffInstances(_)
+                | ToolArgumentValues::DataStoreGet(_)
+                | ToolArgumentValues::DataStoreList(_)
+                | ToolArgumentValues::DataStoreSet(_)
+                | ToolArgumentValues::ListActions(_)
+                | ToolArgumentValues::GetEditMode(_)
+                | ToolArgumentValues::SetEditMode(_)
+                | ToolArgumentValues::SearchMarketplace(_)
+                | ToolArgumentValues::GetDirtyState(_)
+                | ToolArgumentValues::GetSessionInfo(_)
+                | ToolArgumentValues::GetDiagnosticsSummary(_)
+                | ToolArgumentValues::SnapshotProperties(_)
+                | ToolArgumentValues::ListInstalledPlugins(_)
+                | ToolArgumentValues::WorldToScreen(_)
+                | ToolArgumentValues::ScreenToWorld(_)
+                | ToolArgumentValues::GetVelocity(_)
+                | ToolArgumentValues::GetOpenDocuments(_)
+                | ToolArgumentValues::GetPrimaryPart(_)
+                | ToolArgumentValues::GetPerformanceStats(_)
+                | ToolArgumentValues::GetWorkspaceFlags(_)
+                | ToolArgumentValues::ListMyAssets(_)
+                | ToolArgumentValues::Measure(_)
+                | ToolArgumentValues::SetStudioSettings(_)
+                | ToolArgumentValues::SuggestName(_)
+                | ToolArgumentValues::GetRunState(_)
+                | ToolArgumentValues::GetPropertiesBulk(_)
+        )
+    }
+
+    /// How long to wait for Studio to reply to this command, `default` unless the command
+    /// itself asks Studio to wait for longer.
+    fn reply_timeout(&self, default: Duration) -> Duration {
+        match self {
+            ToolArgumentValues::WaitFor(wait_for) => {
+                default.max(Duration::from_millis(wait_for.timeout_ms) + WAIT_FOR_REPLY_MARGIN)
+            }
+            _ => default,
+        }
+    }
+
+    /// Rejects arguments the plugin would only fail on later with a less helpful message, such
+    /// as out of range numbers or fields that are required for the chosen mode.
+    fn validate(&self) -> Result<(), ToolError> {
+        match self {
+            ToolArgumentValues::GetProjectStructure(args) => {
+                check(
+                    matches!(args.detail.as_str(), "minimal" | "detailed"),
+                    "detail must be 'minimal' or 'detailed'",
+                )?;
+                in_range("max_depth", args.max_depth, 1..=20)
+            }
+            ToolArgumentValues::SetCamera(args) => {
+                if let Some(cframe) = &args.cframe {
+                    check(cframe.len() == 12, "cframe must have exactly 12 components")?;
+                    check(
+                        cframe.iter().all(|component| component.is_finite()),
+                        "cframe components must be finite numbers",
+                    )?;
+                }
+                finite_vector("position", args.position.as_ref())?;
+                finite_vector("look_at", args.look_at.as_ref())
+            }
+            ToolArgumentValues::SetPhysicsSettings(args) => {
+                in_range("gravity", args.gravity, f64::MIN..=f64::MAX)?;
+                in_range("air_density", args.air_density, 0.0..=f64::MAX)?;
+                in_range(
+                    "fallen_parts_destroy_height",
+                    args.fallen_parts_destroy_height,
+                    f64::MIN..=f64::MAX,
+                )?;
+                finite_vector("global_wind", args.global_wind.as_ref())
+            }
+            ToolArgumentValues::CountDescendants(args) => in_range("max", args.max, 1..=u32::MAX),
+            ToolArgumentValues::WaitFor(args) => {
+                in_range("timeout_ms", Some(args.timeout_ms), 1..=MAX_WAIT_FOR_MS)
+            }
+            ToolArgumentValues::HighlightInstances(args) => {
+                check(
+                    !args.instance_paths.is_empty(),
+                    "instance_paths must name at least one instance",
+                )?;
+                if let Some(color) = &args.color {
+                    color_components(color)?;
+                }
+                in_range("fill_transparency", args.fill_transparency, 0.0..=1.0)
+            }
+            ToolArgumentValues::SetProperties(args) => check(
+                !args.properties.is_empty(),
+                "properties must contain at least one property",
+            ),
+            ToolArgumentValues::SetPlaceConfig(args) => {
+                streaming_radii(args.streaming_min_radius, args.streaming_target_radius)
+            }
+            ToolArgumentValues::SetWorkspaceFlags(args) => {
+                streaming_radii(args.streaming_min_radius, args.streaming_target_radius)
+            }
+            ToolArgumentValues::GetHistory(args) => in_range("limit", args.limit, 1..=50),
+            ToolArgumentValues::CreateCollisionGroup(args) => collision_group_name(&args.name),
+            ToolArgumentValues::SetCollisionGroup(args) => collision_group_name(&args.group),
+            ToolArgumentValues::SetCollisionGroupCollidable(args) => args
+                .groups
+                .iter()
+                .try_for_each(|name| collision_group_name(name)),
+            ToolArgumentValues::ApplyMaterial(args) => {
+                check(
+                    args.name_pattern.is_some() || args.class_filter.is_some(),
+                    "name_pattern or class_filter is required so not every part is changed",
+                )?;
+                if let Some(color) = &args.color {
+                    color_components(color)?;
+                }
+                Ok(())
+            }
+            ToolArgumentValues::CreateGui(args) => gui_spec(&args.spec, "spec", 0, &mut 0),
+            ToolArgumentValues::CreateGrid(args) => {
+                let counts = &args.counts;
+                check(
+                    counts.x >= 1 && counts.y >= 1 && counts.z >= 1,
+                    "counts must be at least 1 along every axis",
+                )?;
+                let total = u64::from(counts.x) * u64::from(counts.y) * u64::from(counts.z);
+                check(
+                    total <= MAX_GRID_PARTS,
+                    &format!("The grid would create {total} parts, at most {MAX_GRID_PARTS} are allowed per call"),
+                )?;
+                finite_vector("spacing", Some(&args.spacing))?;
+                finite_vector("position", args.position.as_ref())?;
+                finite_vector("template.size", args.template.size.as_ref())?;
+                if let Some(color) = &args.template.color {
+                    color_components(color)?;
+                }
+                Ok(())
+            }
+            ToolArgumentValues::GetDiagnosticsSummary(args) => {
+                in_range("since", args.since, 0.0..=f64::MAX)
+            }
+            ToolArgumentValues::SetAppearance(args) => {
+                check(
+                    args.transparency.is_some()
+                        || args.reflectance.is_some()
+                        || args.material.is_some()
+                        || args.color.is_some()
+                        || args.cast_shadow.is_some(),
+                    "At least one of transparency, reflectance, material, color and cast_shadow is required",
+                )?;
+                in_range("transparency", args.transparency, 0.0..=1.0)?;
+                in_range("reflectance", args.reflectance, 0.0..=1.0)?;
+                if let Some(color) = &args.color {
+                    color_components(color)?;
+                }
+                Ok(())
+            }
+            ToolArgumentValues::SnapshotProperties(args) => {
+                check(!args.name.trim().is_empty(), "name must not be empty")?;
+                check(
+                    (1..=MAX_SNAPSHOT_INSTANCES).contains(&args.instance_paths.len()),
+                    &format!(
+                        "instance_paths must hold between 1 and {MAX_SNAPSHOT_INSTANCES} paths"
+                    ),
+                )?;
+                check(
+                    args.properties.as_ref().is_none_or(|properties| {
+                        (1..=MAX_SNAPSHOT_PROPERTIES).contains(&properties.len())
+                    }),
+                    &format!("properties must hold between 1 and {MAX_SNAPSHOT_PROPERTIES} names"),
+                )
+            }
+            ToolArgumentValues::WorldToScreen(args) => {
+                check(
+                    (1..=MAX_PROJECTED_POINTS).contains(&args.points.len()),
+                    &format!("points must hold between 1 and {MAX_PROJECTED_POINTS} positions"),
+                )?;
+                args.points
+                    .iter()
+                    .try_for_each(|point| finite_vector("points", Some(point)))
+            }
+            ToolArgumentValues::ScreenToWorld(args) => {
+                check(
+                    [args.x, args.y].iter().all(|c| c.is_finite() && *c >= 0.0),
+                    "x and y must be non-negative viewport positions in pixels",
+                )?;
+                in_range(
+                    "max_distance",
+                    args.max_distance,
+                    f64::MIN_POSITIVE..=MAX_SCREEN_RAY_DISTANCE,
+                )
+            }
+            ToolArgumentValues::SetVelocity(args) => {
+                check(
+                    args.linear.is_some() || args.angular.is_some(),
+                    "At least one of linear and angular is required",
+                )?;
+                finite_vector("linear", args.linear.as_ref())?;
+                finite_vector("angular", args.angular.as_ref())
+            }
+            ToolArgumentValues::ApplyImpulse(args) => {
+                check(
+                    args.impulse.is_some() || args.angular_impulse.is_some(),
+                    "At least one of impulse and angular_impulse is required",
+                )?;
+                check(
+                    args.position.is_none() || args.impulse.is_some(),
+                    "position requires impulse",
+                )?;
+                finite_vector("impulse", args.impulse.as_ref())?;
+                finite_vector("position", args.position.as_ref())?;
+                finite_vector("angular_impulse", args.angular_impulse.as_ref())
+            }
+            ToolArgumentValues::RunTest(args) => {
+                check(
+                    (1..=MAX_TEST_ASSERTIONS).contains(&args.assertions.len()),
+                    &format!("assertions must hold between 1 and {MAX_TEST_ASSERTIONS} assertions"),
+                )?;
+                check(
+                    args.assertions
+                        .iter()
+                        .all(|assertion| !assertion.code.trim().is_empty()),
+                    "assertion code must not be empty",
+                )
+            }
+            ToolArgumentValues::CreateParticleEmitter(args) => {
+                effect_appearance(args.color.as_ref(), args.transparency, args.light_emission)?;
+                non_negative("rate", args.rate)?;
+                in_range("lifetime", args.lifetime, 0.0..=20.0)?;
+                check(
+                    args.speed.is_none_or(f64::is_finite),
+                    "speed must be a finite number",
+                )?;
+                non_negative("size", args.size)
+            }
+            ToolArgumentValues::CreateBeam(args) => {
+                effect_appearance(args.color.as_ref(), args.transparency, args.light_emission)?;
+                non_negative("width", args.width)
+            }
+            ToolArgumentValues::CreateTrail(args) => {
+                effect_appearance(args.color.as_ref(), args.transparency, args.light_emission)?;
+                non_negative("lifetime", args.lifetime)
+            }
+            ToolArgumentValues::SetStudioSettings(args) => {
+                check(
+                    args.grid_size
+                        .is_none_or(|size| size.is_finite() && size > 0.0),
+                    "grid_size must be a finite number above 0",
+                )?;
+                in_range("rotate_increment", args.rotate_increment, 0.0..=360.0)
+            }
+            ToolArgumentValues::SuggestName(args) => {
+                check(!args.base_name.is_empty(), "base_name must not be empty")?;
+                check(
+                    !args.base_name.contains('.'),
+                    "base_name must not contain '.', which separates the segments of a path",
+                )
+            }
+            ToolArgumentValues::GetPropertiesBulk(args) => {
+                check(
+                    (1..=MAX_BULK_READ_ENTRIES).contains(&args.entries.len()),
+                    &format!("entries must hold between 1 and {MAX_BULK_READ_ENTRIES} entries"),
+                )?;
+                check(
+                    args.entries
+                        .iter()
+                        .all(|entry| !entry.property_names.is_empty()),
+                    "property_names must not be empty",
+                )
+            }
+            ToolArgumentValues::SearchMarketplace(args) => in_range("limit", args.limit, 1..=30),
+            ToolArgumentValues::ListMyAssets(args) => in_range("limit", args.limit, 1..=100),
+            ToolArgumentValues::DiffInstances(args) => in_range("max_depth", args.max_depth, 0..=5),
+            ToolArgumentValues::DataStoreList(args) => in_range("limit", args.limit, 1..=u32::MAX),
+            ToolArgumentValues::SimulateInput(args) => match args.action {
+                InputAction::Click | InputAction::MouseMove => {
+                    check(
+                        args.x.is_some() && args.y.is_some(),
+                        "x and y are required for click and mouse_move",
+                    )?;
+                    in_range("x", args.x, 0.0..=f64::MAX)?;
+                    in_range("y", args.y, 0.0..=f64::MAX)
+                }
+                InputAction::KeyPress => check(
+                    args.key_code.as_deref().is_some_and(|key| !key.is_empty()),
+                    "key_code is required for key_press",
+                ),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check(condition: bool, message: &str) -> Result<(), ToolError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(ToolError::invalid_arguments(message))
+    }
+}
+
+/// Checks an optional numeric field, NaN is never in range.
+fn in_range<T: PartialOrd + std::fmt::Display>(
+    field: &str,
+    value: Option<T>,
+    range: std::ops::RangeInclusive<T>,
+) -> Result<(), ToolError> {
+    match value {
+        Some(value) if !range.contains(&value) => Err(ToolError::invalid_arguments(format!(
+            "{field} must be between {} and {}, got {value}",
+            range.start(),
+            range.end()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn streaming_radii(min: Option<f64>, target: Option<f64>) -> Result<(), ToolError> {
+    in_range("streaming_min_radius", min, 0.0..=f64::MAX)?;
+    in_range("streaming_target_radius", target, 0.0..=f64::MAX)?;
+    match (min, target) {
+        (Some(min), Some(target)) => check(
+            min <= target,
+            "streaming_min_radius can't be larger than streaming_target_radius",
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn non_negative(field: &str, value: Option<f64>) -> Result<(), ToolError> {
+    check(
+        value.is_none_or(|value| value.is_finite() && value >= 0.0),
+        &format!("{field} must be a finite number of at least 0"),
+    )
+}
+
+/// Checks the appearance properties the effect tools share.
+fn effect_appearance(
+    color: Option<&Color3>,
+    transparency: Option<f64>,
+    light_emission: Option<f64>,
+) -> Result<(), ToolError> {
+    if let Some(color) = color {
+        color_components(color)?;
+    }
+    in_range("transparency", transparency, 0.0..=1.0)?;
+    in_range("light_emission", light_emission, 0.0..=1.0)
+}
+
+fn color_components(color: &Color3) -> Result<(), ToolError> {
+    for (field, value) in [
+        ("color.r", color.r),
+        ("color.g", color.g),
+        ("color.b", color.b),
+    ] {
+        in_range(field, Some(value), 0.0..=1.0)?;
+    }
+    Ok(())
+}
+
+fn collision_group_name(name: &str) -> Result<(), ToolError> {
+    check(
+        !name.trim().is_empty() && name.chars().count() <= MAX_COLLISION_GROUP_NAME_CHARS,
+        &format!(
+            "Collision group names must be between 1 and {MAX_COLLISION_GROUP_NAME_CHARS} characters"
+        ),
+    )
+}
+
+/// Largest number of assertions a single run_test call may run.
+const MAX_TEST_ASSERTIONS: usize = 100;
+
+/// Largest number of points a single world_to_screen call may project.
+const MAX_PROJECTED_POINTS: usize = 100;
+/// Longest ray screen_to_world may cast, in studs.
+const MAX_SCREEN_RAY_DISTANCE: f64 = 10_000.0;
+
+/// Largest number of instances and of properties per instance a snapshot may record.
+const MAX_SNAPSHOT_INSTANCES: usize = 200;
+const MAX_SNAPSHOT_PROPERTIES: usize = 50;
+/// Largest number of instances get_properties_bulk reads in one call.
+const MAX_BULK_READ_ENTRIES: usize = 200;
+
+/// Largest number of parts a single create_grid call may create, more would freeze Studio.
+const MAX_GRID_PARTS: u64 = 1000;
+
+/// Largest number of instances a single create_gui call may create.
+const MAX_GUI_INSTANCES: usize = 200;
+const MAX_GUI_DEPTH: usize = 10;
+
+fn finite_udim2(field: &str, udim2: Option<&UDim2>) -> Result<(), ToolError> {
+    check(
+        udim2.is_none_or(|udim2| {
+            [udim2.x_scale, udim2.x_offset, udim2.y_scale, udim2.y_offset]
+                .iter()
+                .all(|c| c.is_finite())
+        }),
+        &format!("{field} components must be finite numbers"),
+    )
+}
+
+/// Checks a GUI spec and its children, counting the instances it would create in `count`.
+fn gui_spec(spec: &GuiSpec, field: &str, depth: usize, count: &mut usize) -> Result<(), ToolError> {
+    *count += 1;
+    check(
+        *count <= MAX_GUI_INSTANCES,
+        &format!("A GUI spec can create at most {MAX_GUI_INSTANCES} instances"),
+    )?;
+    check(
+        depth <= MAX_GUI_DEPTH,
+        &format!("GUI specs can be nested at most {MAX_GUI_DEPTH} levels deep"),
+    )?;
+    if let Some(name) = &spec.name {
+        check(!name.is_empty(), &format!("{field}.name must not be empty"))?;
+    }
+    if matches!(spec.class_name, GuiClass::ScreenGui) {
+        check(
+            spec.size.is_none() && spec.position.is_none() && spec.background_color.is_none(),
+            &format!("{field} is a ScreenGui, which has no size, position or background_color"),
+        )?;
+    }
+    if spec.text.is_some() {
+        check(
+            matches!(
+                spec.class_name,
+                GuiClass::TextLabel | GuiClass::TextButton | GuiClass::TextBox
+            ),
+            &format!(
+                "{field} is a {:?}, only text objects take text",
+                spec.class_name
+            ),
+        )?;
+    }
+    finite_udim2(&format!("{field}.size"), spec.size.as_ref())?;
+    finite_udim2(&format!("{field}.position"), spec.position.as_ref())?;
+    if let Some(color) = &spec.background_color {
+        color_components(color)?;
+    }
+    for (i, child) in spec.children.iter().enumerate() {
+        gui_spec(child, &format!("{field}.children[{i}]"), depth + 1, count)?;
+    }
+    Ok(())
+}
+
+fn finite_vector(field: &str, vector: Option<&Vector3>) -> Result<(), ToolError> {
+    check(
+        vector.is_none_or(|vector| [vector.x, vector.y, vector.z].iter().all(|c| c.is_finite())),
+        &format!("{field} components must be finite numbers"),
+    )
+}
+
+/// Debug form of a command for the logs. With `redact`, run_code scripts are cut down to their
+/// size and the start of their first line, since debug logs may end up in bug reports.
+struct LoggedCommand<'a> {
+    command: &'a ToolArguments,
+    redact: bool,
+}
+
+impl std::fmt::Debug for LoggedCommand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ToolArgumentValues::RunCode(run_code) = &self.command.args else {
+            return self.command.fmt(f);
+        };
+        if !self.redact {
+            return self.command.fmt(f);
+        }
+        let first_line: String = run_code
+            .command
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take(REDACTED_PREVIEW_CHARS)
+            .collect();
+        let command = format!(
+            "<redacted {} bytes, {} lines, starting with {first_line:?}>",
+            run_code.command.len(),
+            run_code.command.lines().count()
+        );
+        ToolArguments {
+            args: ToolArgumentValues::RunCode(RunCode {
+                command,
+                ..run_code.clone()
+            }),
+            ..self.command.clone()
+        }
+        .fmt(f)
     }
-    fn with_id(self) -> (Self, Uuid) {
+}
+
+impl ToolArguments {
+    /// Creates a command with a fresh id whose caller gives up after `timeout`.
+    fn new(args: ToolArgumentValues, timeout: Duration) -> (Self, Uuid) {
         let id = Uuid::new_v4();
+        let deadline_ms = SystemTime::now()
+            .checked_add(timeout)
+            .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+            .and_then(|deadline| u64::try_from(deadline.as_millis()).ok());
         (
             Self {
-                args: self.args,
                 id: Some(id),
+                deadline_ms,
+                refuse_while_running: false,
+                changes_place: args.changes_place(),
+                args,
             },
             id,
         )
     }
 }
+
+/// Settings for the MCP side of the server, filled in from command line flags.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub instructions: String,
+    pub server_name: String,
+    /// Calls per second allowed for each tool, zero disables rate limiting.
+    pub rate_limit: u32,
+    /// How long a tool call waits for Studio before failing with a timeout.
+    pub tool_timeout: Duration,
+    /// Base directory for relative paths given to file tools.
+    pub workdir: PathBuf,
+    /// Reject file tool paths that resolve outside of `workdir`.
+    pub sandbox_files: bool,
+    /// Text replies longer than this many bytes are truncated, zero disables the limit.
+    pub max_response_bytes: usize,
+    /// Refuse tools that write data living outside the place, such as DataStores.
+    pub read_only: bool,
+    /// Identical run_code calls started within this window share one execution, zero disables.
+    pub run_code_dedup_window: Duration,
+    /// Only these tools are offered when set.
+    pub enabled_tools: Option<Vec<String>>,
+    /// Tools that are never offered, applied after `enabled_tools`.
+    pub disabled_tools: Vec<String>,
+    /// Fail tool calls right away when the plugin isn't connected instead of waiting for it.
+    pub no_wait: bool,
+    /// Let tools that change the place run while Studio is running it.
+    pub allow_edits_while_running: bool,
+    /// Only log the size and first line of run_code scripts.
+    pub redact_logs: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            instructions: DEFAULT_INSTRUCTIONS.to_string(),
+            server_name: DEFAULT_SERVER_NAME.to_string(),
+            rate_limit: DEFAULT_RATE_LIMIT,
+            tool_timeout: Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS),
+            workdir: std::env::current_dir().unwrap_or_default(),
+            sandbox_files: false,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            read_only: false,
+            run_code_dedup_window: Duration::ZERO,
+            enabled_tools: None,
+            disabled_tools: Vec::new(),
+            no_wait: false,
+            allow_edits_while_running: false,
+            redact_logs: false,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Resolves a path passed to a file tool against the working directory, enforcing the
+    /// sandbox if enabled. Symlinks are followed so they can't be used to escape it.
+    async fn resolve_file_path(&self, file_path: &str) -> Result<PathBuf, ToolError> {
+        let path = self.workdir.join(file_path);
+        if !self.sandbox_files {
+            return Ok(path);
+        }
+        let escapes = || {
+            ToolError::invalid_arguments(format!(
+                "{file_path} is outside of the working directory {}",
+                self.workdir.display()
+            ))
+        };
+        let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+            return Err(escapes());
+        };
+        let parent = tokio::fs::canonicalize(parent).await.map_err(|e| {
+            ToolError::invalid_arguments(format!("Can't resolve {}: {e}", parent.display()))
+        })?;
+        if !parent.starts_with(&self.workdir) {
+            return Err(escapes());
+        }
+        let resolved = parent.join(file_name);
+        match tokio::fs::canonicalize(&resolved).await {
+            Ok(target) if !target.starts_with(&self.workdir) => Err(escapes()),
+            _ => Ok(resolved),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RBXStudioServer {
     state: PackedState,
+    config: Arc<ServerConfig>,
+    rate_limiter: Arc<Mutex<RateLimiter<Discriminant<ToolArgumentValues>>>>,
+    /// Replies of `get_class_members` by class name, the API doesn't change during a session.
+    class_members_cache: Arc<Mutex<HashMap<String, String>>>,
+    /// Least severe level of the Studio log lines forwarded to the client.
+    log_level: Arc<Mutex<LoggingLevel>>,
     tool_router: rmcp::handler::server::tool::ToolRouter<Self>,
 }
 
-#[tool_handler]
 impl ServerHandler for RBXStudioServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2025_03_26,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation::from_build_env(),
-            instructions: Some(
-                "User run_command to query data from Roblox Studio place or to change it"
-                    .to_string(),
-            ),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_logging()
+                .build(),
+            server_info: Implementation {
+                name: self.config.server_name.clone(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            instructions: Some(self.config.instructions.clone()),
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let name = request.name.clone();
+        // run_command fills in the command id and outcome once they are known.
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %name,
+            id = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        let tcc = ToolCallContext::new(self, request, context);
+        match self.tool_router.call(tcc).instrument(span).await {
+            // Arguments that fail to deserialize would otherwise surface as a bare serde message,
+            // point the model at what the tool actually expects instead.
+            Err(err) if err.code == ErrorCode::INVALID_PARAMS => {
+                match self.tool_router.map.get(name.as_ref()) {
+                    Some(route) => Ok(ToolError::invalid_arguments(format!(
+                        "Invalid arguments for {name}: {}\n{}",
+                        err.message
+                            .trim_start_matches("failed to deserialize parameters: "),
+                        describe_arguments(&route.attr.input_schema)
+                    ))
+                    .into()),
+                    None => Err(err),
+                }
+            }
+            result => result,
         }
     }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        *self.log_level.lock().await = request.level;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
 struct RunCode {
-    #[schemars(description = "Code to run")]
+    #[schemars(description = "Code to run", example = &"print(#workspace:GetChildren())")]
     command: String,
+    #[schemars(
+        description = "Forward everything Studio logs while the code runs, including warnings and errors from other scripts, as MCP logging notifications as it happens. Defaults to false"
+    )]
+    stream_logs: Option<bool>,
 }
+
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
 struct InsertModel {
-    #[schemars(description = "Query to search for the model")]
+    #[schemars(description = "Query to search for the model", example = &"wooden chair")]
     query: String,
+    #[schemars(
+        description = "Path of the instance to insert into (default: Workspace)",
+        example = &"ReplicatedStorage.Assets"
+    )]
+    parent_path: Option<String>,
 }
 
 // BEGIN ADDITION
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct Vector3 {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+/// RGB color with components between 0 and 1, like `Color3.new`.
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct Color3 {
+    r: f64,
+    g: f64,
+    b: f64,
+}
+
+/// Size or position of a GUI object, like `UDim2.new`: a fraction of the parent plus pixels.
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct UDim2 {
+    x_scale: f64,
+    x_offset: f64,
+    y_scale: f64,
+    y_offset: f64,
+}
+
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
 struct DeletePart {
-    #[schemars(description = "Name of the part to delete")]
+    #[schemars(description = "Name of the part to delete", example = &"Part1")]
     part_name: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
 struct GetProjectStructure {
-    #[schemars(description = "Detail level: 'minimal' or 'detailed'")]
+    #[schemars(description = "Detail level: 'minimal' or 'detailed'", example = &"minimal")]
     detail: String,
-    #[schemars(description = "Maximum traversal depth (default: 5, max: 20)")]
+    #[schemars(
+        description = "Maximum traversal depth (default: 5, max: 20)",
+        example = 3
+    )]
     max_depth: Option<u32>,
-    #[schemars(description = "Root path to start from (e.g. 'Workspace.Model1')")]
+    #[schemars(
+        description = "Root path to start from (e.g. 'Workspace.Model1')",
+        example = &"Workspace.Model1"
+    )]
     root_path: Option<String>,
+    #[schemars(
+        description = "etag returned by an earlier call, a short not modified reply is returned if the structure is unchanged"
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    if_none_match: Option<String>,
 }
 
-// END ADDITION
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ReplaceInScripts {
+    #[schemars(description = "Text to search for in script sources", example = &"wait(")]
+    find: String,
+    #[schemars(description = "Text to replace each match with", example = &"task.wait(")]
+    replace: String,
+    #[schemars(
+        description = "Treat `find` as a Luau string pattern instead of plain text (default: false)"
+    )]
+    use_pattern: Option<bool>,
+    #[schemars(
+        description = "Root path to search under (e.g. 'ServerScriptService'), defaults to the whole game"
+    )]
+    root_path: Option<String>,
+    #[schemars(
+        description = "Only search scripts of this class, e.g. 'ModuleScript' (default: all scripts)"
+    )]
+    class_name: Option<ClassName>,
+    #[schemars(description = "Only report replacement counts without changing any script")]
+    dry_run: Option<bool>,
+}
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
-enum ToolArgumentValues {
-    RunCode(RunCode),
-    InsertModel(InsertModel),
-    DeletePart(DeletePart),
-    GetProjectStructure(GetProjectStructure),
+struct ShowMessage {
+    #[schemars(
+        description = "Text to display to the user in the MCP status widget",
+        example = &"Building the obby, this takes a minute"
+    )]
+    message: String,
+    #[schemars(description = "Optional widget title (default: 'MCP Status')")]
+    title: Option<String>,
 }
 
-#[tool_router]
-impl RBXStudioServer {
-    pub fn new(state: PackedState) -> Self {
-        Self {
-            state,
-            tool_router: Self::tool_router(),
-        }
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetCamera {}
 
-    #[tool(
-        description = "Runs a command in Roblox Studio and returns the printed output. Can be used to both make changes and retrieve information"
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetCamera {
+    #[schemars(description = "New camera position, keeps the current one when omitted")]
+    position: Option<Vector3>,
+    #[schemars(description = "World point the camera should look at")]
+    look_at: Option<Vector3>,
+    #[schemars(
+        description = "Full CFrame as 12 components (as returned by get_camera), overrides position and look_at"
     )]
-    async fn run_code(
-        &self,
-        Parameters(args): Parameters<RunCode>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::RunCode(args))
-            .await
-    }
+    cframe: Option<Vec<f64>>,
+}
 
-    #[tool(
-        description = "Inserts a model from the Roblox marketplace into the workspace. Returns the inserted model name."
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FocusInstance {
+    #[schemars(
+        description = "Path of the Model or BasePart to focus on (e.g. 'Workspace.Model1')",
+        example = &"Workspace.Model1"
     )]
-    async fn insert_model(
-        &self,
+    instance_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct InsertLocalModel {
+    #[schemars(
+        description = "Path to a .rbxm or .rbxmx file readable by the MCP server process, relative paths are resolved against its working directory",
+        example = &"models/Tree.rbxm"
+    )]
+    file_path: String,
+    #[schemars(description = "Path of the instance to insert into (default: Workspace)")]
+    parent_path: Option<String>,
+}
+
+/// Payload sent to the plugin for `insert_local_model`, the file contents are read server-side.
+#[derive(Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct InsertLocalModelData {
+    file_name: String,
+    data: String,
+    parent_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ExportModel {
+    #[schemars(
+        description = "Path of the instance to export together with its descendants",
+        example = &"Workspace.Model1"
+    )]
+    instance_path: String,
+    #[schemars(
+        description = "Destination .rbxm file path, written by the MCP server process relative to its working directory",
+        example = &"exports/Model1.rbxm"
+    )]
+    file_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct Ping {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListServices {}
+
+impl std::fmt::Debug for InsertLocalModelData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InsertLocalModelData")
+            .field("file_name", &self.file_name)
+            .field("data", &format_args!("<{} base64 chars>", self.data.len()))
+            .field("parent_path", &self.parent_path)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetStudioVersion {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetPhysicsSettings {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetPhysicsSettings {
+    #[schemars(description = "Workspace.Gravity in studs/s^2 (Studio default: 196.2)")]
+    gravity: Option<f64>,
+    #[schemars(description = "Workspace.AirDensity used by aerodynamic forces")]
+    air_density: Option<f64>,
+    #[schemars(description = "Workspace.GlobalWind direction and speed")]
+    global_wind: Option<Vector3>,
+    #[schemars(description = "Workspace.FallenPartsDestroyHeight, parts below it are destroyed")]
+    fallen_parts_destroy_height: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CountDescendants {
+    #[schemars(
+        description = "Path of the instance whose descendants to count (e.g. 'Workspace')",
+        example = &"Workspace"
+    )]
+    instance_path: String,
+    #[schemars(
+        description = "Stop counting once this many descendants have been found, to avoid walking huge trees"
+    )]
+    max: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct WaitFor {
+    #[schemars(
+        description = "Luau expression that evaluates to a truthy value once the condition holds (e.g. workspace:FindFirstChild('Map') ~= nil). A chunk with an explicit return also works",
+        example = &"workspace:FindFirstChild('Map') ~= nil"
+    )]
+    predicate: String,
+    #[schemars(
+        description = "How long to wait for the predicate in milliseconds",
+        example = 5000
+    )]
+    timeout_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct HighlightInstances {
+    #[schemars(description = "Paths of the instances to highlight (e.g. ['Workspace.Model1'])")]
+    instance_paths: Vec<String>,
+    #[schemars(description = "Outline and fill color (default: orange)")]
+    color: Option<Color3>,
+    #[schemars(description = "Transparency of the fill between 0 and 1 (default: 0.5)")]
+    fill_transparency: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ClearHighlights {
+    #[schemars(
+        description = "Handles returned by highlight_instances to remove, all highlights are cleared when omitted"
+    )]
+    handles: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ResolvePath {
+    #[schemars(
+        description = "Possibly imperfect instance path to look up (e.g. 'game.workspace.part1')",
+        example = &"game.workspace.part1"
+    )]
+    path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetProperties {
+    #[schemars(
+        description = "Path of the instance to change (e.g. 'Workspace.Part1')",
+        example = &"Workspace.Part1"
+    )]
+    instance_path: String,
+    #[schemars(
+        description = "Property names mapped to their new values. Vectors and colors are objects such as {x, y, z} or {r, g, b} (or a '#rrggbb' string), enums are item names, CFrames are 12 numbers and instance references are paths",
+        example = serde_json::json!({"Anchored": true, "Material": "Neon"})
+    )]
+    properties: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetClassMembers {
+    #[schemars(
+        description = "Name of the Roblox class to describe (e.g. 'Part')",
+        example = &"Part"
+    )]
+    class_name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CancelAll {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetPlaceConfig {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetPlaceConfig {
+    #[schemars(description = "Workspace.StreamingEnabled, turns on instance streaming")]
+    streaming_enabled: Option<bool>,
+    #[schemars(description = "Workspace.StreamingMinRadius in studs")]
+    streaming_min_radius: Option<f64>,
+    #[schemars(description = "Workspace.StreamingTargetRadius in studs")]
+    streaming_target_radius: Option<f64>,
+    #[schemars(description = "Lighting.Technology, the lighting engine used to render the place")]
+    lighting_technology: Option<LightingTechnology>,
+    #[schemars(
+        description = "SoundService.RespectFilteringEnabled, whether client sounds replicate"
+    )]
+    respect_filtering_enabled: Option<bool>,
+}
+
+/// Value stored by the `kv_*` tools, kept to types that attributes can hold.
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+#[serde(untagged)]
+enum KvValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct KvSet {
+    #[schemars(
+        description = "Key to store the value under, letters, digits and underscores only",
+        example = &"last_built_level"
+    )]
+    key: String,
+    #[schemars(description = "String, number or boolean to store")]
+    value: KvValue,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct KvGet {
+    #[schemars(description = "Key to read", example = &"last_built_level")]
+    key: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct KvList {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetHistory {
+    #[schemars(description = "Maximum number of waypoints to return (default: 50)")]
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+enum WeldType {
+    Weld,
+    WeldConstraint,
+    Motor6D,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct WeldParts {
+    #[schemars(
+        description = "Paths of the two BaseParts to join, the joint is parented to the first one",
+        example = ["Workspace.Car.Body", "Workspace.Car.Wheel"]
+    )]
+    instance_paths: [String; 2],
+    #[schemars(description = "Kind of joint to create (default: WeldConstraint)")]
+    weld_type: Option<WeldType>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetBoundingBox {
+    #[schemars(
+        description = "Path of the Model, BasePart or folder to measure, the current selection is used when omitted",
+        example = &"Workspace.House"
+    )]
+    instance_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct DiffInstances {
+    #[schemars(
+        description = "Paths of the two instances to compare, typically a template and an edited copy",
+        example = ["ServerStorage.Templates.Door", "Workspace.House.Door"]
+    )]
+    instance_paths: [String; 2],
+    #[schemars(description = "How many levels of children to compare (default: 2, max: 5)")]
+    max_depth: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetSelectionProperty {
+    #[schemars(description = "Name of the property to set", example = &"Color")]
+    property_name: String,
+    #[schemars(
+        description = "New value, in the same format as set_properties (e.g. {r, g, b} or '#rrggbb' for colors)",
+        example = &"#ff0000"
+    )]
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct DataStoreGet {
+    #[schemars(description = "Name of the DataStore", example = &"PlayerData")]
+    store_name: String,
+    #[schemars(description = "Key to read", example = &"Player_1234")]
+    key: String,
+    #[schemars(description = "DataStore scope (default: global)")]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct DataStoreList {
+    #[schemars(
+        description = "DataStore whose keys to list, omit to list the DataStores of the experience",
+        example = &"PlayerData"
+    )]
+    store_name: Option<String>,
+    #[schemars(description = "Only list keys or DataStore names starting with this prefix")]
+    prefix: Option<String>,
+    #[schemars(description = "DataStore scope (default: global)")]
+    scope: Option<String>,
+    #[schemars(description = "Maximum number of entries to return (default: 100)")]
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct DataStoreSet {
+    #[schemars(description = "Name of the DataStore", example = &"PlayerData")]
+    store_name: String,
+    #[schemars(description = "Key to write", example = &"Player_1234")]
+    key: String,
+    #[schemars(
+        description = "JSON value to store",
+        example = serde_json::json!({"coins": 100})
+    )]
+    value: serde_json::Value,
+    #[schemars(description = "DataStore scope (default: global)")]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListActions {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct TriggerAction {
+    #[schemars(
+        description = "Id of the action to fire, as returned by list_actions",
+        example = &"group_selection"
+    )]
+    action_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+#[serde(rename_all = "snake_case")]
+enum InputAction {
+    Click,
+    MouseMove,
+    KeyPress,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SimulateInput {
+    #[schemars(description = "Interaction to inject")]
+    action: InputAction,
+    #[schemars(description = "Horizontal viewport position in pixels, for click and mouse_move")]
+    x: Option<f64>,
+    #[schemars(description = "Vertical viewport position in pixels, for click and mouse_move")]
+    y: Option<f64>,
+    #[schemars(
+        description = "Enum.KeyCode item name, for key_press",
+        example = &"Space"
+    )]
+    key_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetEditMode {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetEditMode {
+    #[schemars(description = "Editing tool to activate", example = &"Move")]
+    mode: RibbonTool,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SearchMarketplace {
+    #[schemars(description = "Query to search free models for", example = &"wooden chair")]
+    query: String,
+    #[schemars(description = "Maximum number of results to return (default: 10, max: 30)")]
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct InsertModelByAssetId {
+    #[schemars(
+        description = "Id of the asset to insert, as returned by search_marketplace or list_my_assets",
+        example = 12345678
+    )]
+    asset_id: u64,
+    #[schemars(description = "Name for the inserted model (default: the asset's name)")]
+    name: Option<String>,
+    #[schemars(description = "Path of the instance to insert into (default: Workspace)")]
+    parent_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateCollisionGroup {
+    #[schemars(description = "Name of the collision group to register", example = &"Players")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetCollisionGroup {
+    #[schemars(
+        description = "Path of a BasePart, or of a model or folder whose BaseParts all join the group",
+        example = &"Workspace.Door"
+    )]
+    instance_path: String,
+    #[schemars(description = "Registered collision group to assign", example = &"Players")]
+    group: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetCollisionGroupCollidable {
+    #[schemars(description = "Names of the two collision groups", example = ["Players", "Doors"])]
+    groups: [String; 2],
+    #[schemars(description = "Whether parts of the two groups collide with each other")]
+    collidable: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ApplyMaterial {
+    #[schemars(
+        description = "Luau string pattern matched against part names, e.g. '^Wall' (at least one of name_pattern and class_filter is required)",
+        example = &"^Wall"
+    )]
+    name_pattern: Option<String>,
+    #[schemars(description = "Only change parts of this class, e.g. 'MeshPart'")]
+    class_filter: Option<ClassName>,
+    #[schemars(description = "Material to apply", example = &"Brick")]
+    material: Material,
+    #[schemars(description = "Color to apply as well, keeps each part's color when omitted")]
+    color: Option<Color3>,
+    #[schemars(description = "Path to search under (default: Workspace)")]
+    root_path: Option<String>,
+    #[schemars(description = "Only report how many parts match without changing them")]
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetDirtyState {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RequireModule {
+    #[schemars(
+        description = "Path of the ModuleScript to require (e.g. 'ReplicatedStorage.Shared.Util')",
+        example = &"ReplicatedStorage.Shared.Util"
+    )]
+    instance_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+enum GuiClass {
+    ScreenGui,
+    Frame,
+    ScrollingFrame,
+    TextLabel,
+    TextButton,
+    TextBox,
+    ImageLabel,
+    ImageButton,
+    UIListLayout,
+    UIGridLayout,
+    UIPadding,
+    UICorner,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GuiSpec {
+    #[schemars(description = "Class of the GUI object to create")]
+    class_name: GuiClass,
+    #[schemars(description = "Name of the object (default: the class name)")]
+    name: Option<String>,
+    #[schemars(description = "Size as UDim2 components, not allowed for ScreenGui")]
+    size: Option<UDim2>,
+    #[schemars(description = "Position as UDim2 components, not allowed for ScreenGui")]
+    position: Option<UDim2>,
+    #[schemars(description = "Text shown by a TextLabel, TextButton or TextBox")]
+    text: Option<String>,
+    #[schemars(description = "Background color with components between 0 and 1")]
+    background_color: Option<Color3>,
+    #[schemars(description = "GUI objects to create inside this one")]
+    #[serde(default)]
+    children: Vec<GuiSpec>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateGui {
+    #[schemars(
+        description = "Path of the instance to create the GUI under (default: 'StarterGui')",
+        example = &"StarterGui"
+    )]
+    parent_path: Option<String>,
+    #[schemars(description = "Root GUI object to create, with its children")]
+    spec: GuiSpec,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FindDuplicates {
+    #[schemars(description = "Path to search under (default: Workspace)")]
+    root_path: Option<String>,
+    #[schemars(
+        description = "Only report the duplicates (default: true). Set to false to delete every duplicate but the first of each set"
+    )]
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RemoveEmpty {
+    #[schemars(description = "Path to search under (default: Workspace)")]
+    root_path: Option<String>,
+    #[schemars(
+        description = "Only report the empty Folders and Models (default: true). Set to false to delete them"
+    )]
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetSessionInfo {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GridCounts {
+    x: u32,
+    y: u32,
+    z: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct PartTemplate {
+    #[schemars(description = "Size of each part in studs (default: 4, 1, 2)")]
+    size: Option<Vector3>,
+    #[schemars(description = "Color with components between 0 and 1")]
+    color: Option<Color3>,
+    #[schemars(description = "Material of each part", example = &"Plastic")]
+    material: Option<Material>,
+    #[schemars(description = "Whether the parts are anchored (default: true)")]
+    anchored: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateGrid {
+    #[schemars(description = "Number of parts along each axis, at most 1000 parts in total")]
+    counts: GridCounts,
+    #[schemars(
+        description = "Distance in studs between the centers of neighbouring parts along each axis"
+    )]
+    spacing: Vector3,
+    #[schemars(
+        description = "Center of the first part, the grid extends along the positive axes (default: the origin)"
+    )]
+    position: Option<Vector3>,
+    #[schemars(description = "Size, color and material shared by every part")]
+    template: PartTemplate,
+    #[schemars(description = "Path to create the Model under (default: Workspace)")]
+    parent_path: Option<String>,
+    #[schemars(description = "Name of the Model holding the parts (default: 'Grid')")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetScriptEnabled {
+    #[schemars(
+        description = "Path of the Script or LocalScript, or with recursive of a container holding scripts",
+        example = &"ServerScriptService.Spawner"
+    )]
+    instance_path: String,
+    #[schemars(description = "Whether the scripts should run")]
+    enabled: bool,
+    #[schemars(description = "Also toggle every Script and LocalScript under instance_path")]
+    recursive: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetDiagnosticsSummary {
+    #[schemars(
+        description = "Only count output logged after this Unix timestamp, e.g. the latest_timestamp of a previous call"
+    )]
+    since: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetAppearance {
+    #[schemars(description = "Path of the BasePart to change", example = &"Workspace.Wall")]
+    instance_path: String,
+    #[schemars(description = "Transparency between 0 (opaque) and 1 (invisible)")]
+    transparency: Option<f64>,
+    #[schemars(description = "Reflectance between 0 and 1")]
+    reflectance: Option<f64>,
+    #[schemars(description = "Material of the part", example = &"Glass")]
+    material: Option<Material>,
+    #[schemars(description = "Color with components between 0 and 1")]
+    color: Option<Color3>,
+    #[schemars(description = "Whether the part casts a shadow")]
+    cast_shadow: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SnapshotProperties {
+    #[schemars(description = "Name to restore the snapshot by, taking a snapshot with an existing name replaces it", example = &"before_lighting_pass")]
+    name: String,
+    #[schemars(description = "Paths of the instances whose properties to record, at most 200")]
+    instance_paths: Vec<String>,
+    #[schemars(
+        description = "Properties to record on each instance (default: common properties such as CFrame, Size, Color, Material, Transparency, Text and Enabled)"
+    )]
+    properties: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RestoreSnapshot {
+    #[schemars(description = "Name the snapshot was taken with")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListInstalledPlugins {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct WorldToScreen {
+    #[schemars(description = "World positions to project onto the viewport, at most 100")]
+    points: Vec<Vector3>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ScreenToWorld {
+    #[schemars(description = "Horizontal viewport position in pixels, as used by simulate_input")]
+    x: f64,
+    #[schemars(description = "Vertical viewport position in pixels, as used by simulate_input")]
+    y: f64,
+    #[schemars(description = "How far to cast the ray in studs (default: 1000)")]
+    max_distance: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetVelocity {
+    #[schemars(
+        description = "Path of the BasePart, or Model with a PrimaryPart, whose assembly to read",
+        example = &"Workspace.Ball"
+    )]
+    instance_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetVelocity {
+    #[schemars(
+        description = "Path of the BasePart, or Model with a PrimaryPart, whose assembly to set the velocity of"
+    )]
+    instance_path: String,
+    #[schemars(description = "Linear velocity in studs per second, left unchanged when omitted")]
+    linear: Option<Vector3>,
+    #[schemars(
+        description = "Angular velocity in radians per second, left unchanged when omitted"
+    )]
+    angular: Option<Vector3>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ApplyImpulse {
+    #[schemars(
+        description = "Path of the BasePart, or Model with a PrimaryPart, whose assembly to push"
+    )]
+    instance_path: String,
+    #[schemars(description = "Linear impulse in mass times studs per second")]
+    impulse: Option<Vector3>,
+    #[schemars(
+        description = "World position to apply impulse at, which also spins the assembly when off its center of mass (default: the center of mass)"
+    )]
+    position: Option<Vector3>,
+    #[schemars(description = "Angular impulse to spin the assembly with")]
+    angular_impulse: Option<Vector3>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetOpenDocuments {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct TestAssertion {
+    #[schemars(description = "Name to report the assertion under (default: its position)")]
+    name: Option<String>,
+    #[schemars(
+        description = "Luau code that fails the assertion by raising an error or returning false",
+        example = &"assert(workspace:FindFirstChild(\"Door\"), \"Door is missing\")"
+    )]
+    code: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RunTest {
+    #[schemars(
+        description = "Luau code run before the assertions, globals it defines are visible to them. The assertions are skipped if it fails"
+    )]
+    setup: Option<String>,
+    #[schemars(description = "Assertions to run in order, at most 100")]
+    assertions: Vec<TestAssertion>,
+    #[schemars(
+        description = "Luau code run after the assertions even when they fail, to clean up"
+    )]
+    teardown: Option<String>,
+    #[schemars(
+        description = "Skip the remaining assertions after the first failure (default: false)"
+    )]
+    stop_on_failure: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetPrimaryPart {
+    #[schemars(description = "Path of the Model", example = &"Workspace.Car")]
+    model_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetPrimaryPart {
+    #[schemars(description = "Path of the Model", example = &"Workspace.Car")]
+    model_path: String,
+    #[schemars(
+        description = "Path of the BasePart to make the PrimaryPart, must be a descendant of the model",
+        example = &"Workspace.Car.Chassis"
+    )]
+    part_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetPerformanceStats {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateParticleEmitter {
+    #[schemars(
+        description = "Path of the part or attachment to emit from",
+        example = &"Workspace.Campfire.Logs"
+    )]
+    parent_path: String,
+    #[schemars(description = "Name of the new instance (default: its class name)")]
+    name: Option<String>,
+    #[schemars(description = "Color over the effect's whole life")]
+    color: Option<Color3>,
+    #[schemars(description = "Texture asset id", example = &"rbxassetid://243098098")]
+    texture: Option<String>,
+    #[schemars(description = "Transparency between 0 and 1 over the effect's whole life")]
+    transparency: Option<f64>,
+    #[schemars(
+        description = "How much the effect adds light to what is behind it, between 0 and 1"
+    )]
+    light_emission: Option<f64>,
+    #[schemars(description = "Particles emitted per second")]
+    rate: Option<f64>,
+    #[schemars(description = "Seconds each particle lives, between 0 and 20")]
+    lifetime: Option<f64>,
+    #[schemars(description = "Speed particles are emitted at in studs per second")]
+    speed: Option<f64>,
+    #[schemars(description = "Particle size in studs over their whole life")]
+    size: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateBeam {
+    #[schemars(description = "Path of the instance to create the Beam under, usually a part")]
+    parent_path: String,
+    #[schemars(description = "Path of the Attachment the beam starts at")]
+    attachment0_path: String,
+    #[schemars(description = "Path of the Attachment the beam ends at")]
+    attachment1_path: String,
+    #[schemars(description = "Name of the new instance (default: its class name)")]
+    name: Option<String>,
+    #[schemars(description = "Color over the effect's whole life")]
+    color: Option<Color3>,
+    #[schemars(description = "Texture asset id", example = &"rbxassetid://243098098")]
+    texture: Option<String>,
+    #[schemars(description = "Transparency between 0 and 1 over the effect's whole life")]
+    transparency: Option<f64>,
+    #[schemars(
+        description = "How much the effect adds light to what is behind it, between 0 and 1"
+    )]
+    light_emission: Option<f64>,
+    #[schemars(description = "Width of the beam in studs at both ends")]
+    width: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateTrail {
+    #[schemars(
+        description = "Path of the instance to create the Trail under, usually the moving part"
+    )]
+    parent_path: String,
+    #[schemars(description = "Path of one of the two Attachments the trail is drawn between")]
+    attachment0_path: String,
+    #[schemars(description = "Path of the other Attachment the trail is drawn between")]
+    attachment1_path: String,
+    #[schemars(description = "Name of the new instance (default: its class name)")]
+    name: Option<String>,
+    #[schemars(description = "Color over the effect's whole life")]
+    color: Option<Color3>,
+    #[schemars(description = "Texture asset id", example = &"rbxassetid://243098098")]
+    texture: Option<String>,
+    #[schemars(description = "Transparency between 0 and 1 over the effect's whole life")]
+    transparency: Option<f64>,
+    #[schemars(
+        description = "How much the effect adds light to what is behind it, between 0 and 1"
+    )]
+    light_emission: Option<f64>,
+    #[schemars(description = "Seconds each segment of the trail stays visible")]
+    lifetime: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetWorkspaceFlags {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetWorkspaceFlags {
+    #[schemars(description = "Workspace.StreamingEnabled, turns on instance streaming")]
+    streaming_enabled: Option<bool>,
+    #[schemars(description = "Workspace.StreamingMinRadius in studs")]
+    streaming_min_radius: Option<f64>,
+    #[schemars(description = "Workspace.StreamingTargetRadius in studs")]
+    streaming_target_radius: Option<f64>,
+    #[schemars(
+        description = "Workspace.StreamingIntegrityMode, what clients do while the area around them hasn't streamed in"
+    )]
+    streaming_integrity_mode: Option<StreamingIntegrityMode>,
+    #[schemars(
+        description = "Workspace.PhysicsSteppingMethod, how the physics solver picks its time step"
+    )]
+    physics_stepping_method: Option<PhysicsSteppingMethod>,
+    #[schemars(
+        description = "Workspace.TouchesUseCollisionGroups, whether parts in groups that don't collide also stop firing Touched"
+    )]
+    touches_use_collision_groups: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListMyAssets {
+    #[schemars(description = "Maximum number of assets to return (default: 50, max: 100)")]
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct Measure {
+    #[schemars(
+        description = "Paths of the two parts, models or attachments to measure between, a then b",
+        example = ["Workspace.House.Door", "Workspace.SpawnLocation"]
+    )]
+    instance_paths: [String; 2],
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetStudioSettings {
+    #[schemars(description = "Grid snap increment in studs for moving parts with the draggers")]
+    grid_size: Option<f64>,
+    #[schemars(description = "Rotate snap increment in degrees, between 0 and 360")]
+    rotate_increment: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SuggestName {
+    #[schemars(
+        description = "Path of the instance the new child will be created under",
+        example = &"Workspace.Map"
+    )]
+    parent_path: String,
+    #[schemars(description = "Name to start from", example = &"Part")]
+    base_name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetRunState {}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct PropertyRead {
+    #[schemars(description = "Path of the instance to read", example = &"Workspace.Door")]
+    instance_path: String,
+    #[schemars(description = "Properties to read on the instance", example = &["Position", "Anchored"])]
+    property_names: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetPropertiesBulk {
+    #[schemars(description = "Instances and the properties to read on each, at most 200 entries")]
+    entries: Vec<PropertyRead>,
+}
+
+// END ADDITION
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+enum ToolArgumentValues {
+    RunCode(RunCode),
+    InsertModel(InsertModel),
+    DeletePart(DeletePart),
+    GetProjectStructure(GetProjectStructure),
+    ReplaceInScripts(ReplaceInScripts),
+    ShowMessage(ShowMessage),
+    GetCamera(GetCamera),
+    SetCamera(SetCamera),
+    FocusInstance(FocusInstance),
+    InsertLocalModel(InsertLocalModelData),
+    ExportModel(ExportModel),
+    Ping(Ping),
+    ListServices(ListServices),
+    GetStudioVersion(GetStudioVersion),
+    GetPhysicsSettings(GetPhysicsSettings),
+    SetPhysicsSettings(SetPhysicsSettings),
+    CountDescendants(CountDescendants),
+    WaitFor(WaitFor),
+    HighlightInstances(HighlightInstances),
+    ClearHighlights(ClearHighlights),
+    ResolvePath(ResolvePath),
+    SetProperties(SetProperties),
+    GetClassMembers(GetClassMembers),
+    GetPlaceConfig(GetPlaceConfig),
+    SetPlaceConfig(SetPlaceConfig),
+    KvSet(KvSet),
+    KvGet(KvGet),
+    KvList(KvList),
+    GetHistory(GetHistory),
+    WeldParts(WeldParts),
+    GetBoundingBox(GetBoundingBox),
+    DiffInstances(DiffInstances),
+    SetSelectionProperty(SetSelectionProperty),
+    DataStoreGet(DataStoreGet),
+    DataStoreList(DataStoreList),
+    DataStoreSet(DataStoreSet),
+    ListActions(ListActions),
+    TriggerAction(TriggerAction),
+    SimulateInput(SimulateInput),
+    GetEditMode(GetEditMode),
+    SetEditMode(SetEditMode),
+    SearchMarketplace(SearchMarketplace),
+    InsertModelByAssetId(InsertModelByAssetId),
+    CreateCollisionGroup(CreateCollisionGroup),
+    SetCollisionGroup(SetCollisionGroup),
+    SetCollisionGroupCollidable(SetCollisionGroupCollidable),
+    ApplyMaterial(ApplyMaterial),
+    GetDirtyState(GetDirtyState),
+    RequireModule(RequireModule),
+    CreateGui(CreateGui),
+    FindDuplicates(FindDuplicates),
+    RemoveEmpty(RemoveEmpty),
+    GetSessionInfo(GetSessionInfo),
+    CreateGrid(CreateGrid),
+    SetScriptEnabled(SetScriptEnabled),
+    GetDiagnosticsSummary(GetDiagnosticsSummary),
+    SetAppearance(SetAppearance),
+    SnapshotProperties(SnapshotProperties),
+    RestoreSnapshot(RestoreSnapshot),
+    ListInstalledPlugins(ListInstalledPlugins),
+    WorldToScreen(WorldToScreen),
+    ScreenToWorld(ScreenToWorld),
+    GetVelocity(GetVelocity),
+    SetVelocity(SetVelocity),
+    ApplyImpulse(ApplyImpulse),
+    GetOpenDocuments(GetOpenDocuments),
+    RunTest(RunTest),
+    GetPrimaryPart(GetPrimaryPart),
+    SetPrimaryPart(SetPrimaryPart),
+    GetPerformanceStats(GetPerformanceStats),
+    CreateParticleEmitter(CreateParticleEmitter),
+    CreateBeam(CreateBeam),
+    CreateTrail(CreateTrail),
+    GetWorkspaceFlags(GetWorkspaceFlags),
+    SetWorkspaceFlags(SetWorkspaceFlags),
+    ListMyAssets(ListMyAssets),
+    Measure(Measure),
+    SetStudioSettings(SetStudioSettings),
+    SuggestName(SuggestName),
+    GetRunState(GetRunState),
+    GetPropertiesBulk(GetPropertiesBulk),
+}
+
+#[tool_router]
+impl RBXStudioServer {
+    pub fn new(state: PackedState, config: ServerConfig) -> color_eyre::Result<Self> {
+        let mut tool_router = Self::tool_router();
+        // Filtered out of the router rather than rejected on call, so clients never see them.
+        for name in config
+            .enabled_tools
+            .iter()
+            .flatten()
+            .chain(&config.disabled_tools)
+        {
+            if !tool_router.has_route(name) {
+                let mut known: Vec<&str> = tool_router.map.keys().map(AsRef::as_ref).collect();
+                known.sort_unstable();
+                color_eyre::eyre::bail!(
+                    "Unknown tool {name}, expected one of {}",
+                    known.join(", ")
+                );
+            }
+        }
+        if let Some(enabled) = &config.enabled_tools {
+            tool_router
+                .map
+                .retain(|name, _| enabled.iter().any(|enabled| enabled == name));
+        }
+        tool_router.map.retain(|name, _| {
+            !config
+                .disabled_tools
+                .iter()
+                .any(|disabled| disabled == name)
+        });
+        Ok(Self {
+            state,
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new(config.rate_limit))),
+            config: Arc::new(config),
+            class_members_cache: Arc::new(Mutex::new(HashMap::new())),
+            log_level: Arc::new(Mutex::new(LoggingLevel::Debug)),
+            tool_router,
+        })
+    }
+
+    #[tool(
+        description = "Runs a command in Roblox Studio and returns the printed output. Can be used to both make changes and retrieve information"
+    )]
+    async fn run_code(
+        &self,
+        Parameters(args): Parameters<RunCode>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.run_code_deduplicated(args, context).await
+    }
+
+    #[tool(
+        description = "Inserts a model from the Roblox marketplace into the workspace or under parent_path. Returns the full path of the inserted model."
+    )]
+    async fn insert_model(
+        &self,
         Parameters(args): Parameters<InsertModel>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::InsertModel(args), context)
+            .await
+    }
+
+    // BEGIN ADDITION
+    #[tool(description = "Deletes a part from the workspace by name")]
+    async fn delete_part(
+        &self,
+        Parameters(args): Parameters<DeletePart>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::DeletePart(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Gets project structure with configurable detail level. The reply ends with an etag, pass it back as if_none_match to get a short not modified reply while nothing changed"
+    )]
+    async fn get_project_structure(
+        &self,
+        Parameters(mut args): Parameters<GetProjectStructure>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Only compared here, the plugin never sees it.
+        let if_none_match = args.if_none_match.take();
+        match self
+            .run_command(ToolArgumentValues::GetProjectStructure(args), &context)
+            .await
+        {
+            Ok(structure) => Ok(etag_reply(
+                structure.into_text(),
+                if_none_match.as_deref(),
+                self.config.max_response_bytes,
+            )),
+            Err(err) => Ok(self.reply(Err(err))),
+        }
+    }
+
+    #[tool(
+        description = "Searches and replaces text across script sources, returning a list of the scripts changed with their path and replacement count, and under failed the scripts whose source couldn't be written with the error. total only counts replacements that were written. Use dry_run to preview counts without changing anything"
+    )]
+    async fn replace_in_scripts(
+        &self,
+        Parameters(args): Parameters<ReplaceInScripts>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ReplaceInScripts(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Shows a message to the user in a Studio dock widget. Use it to surface progress or status of longer tasks"
+    )]
+    async fn show_message(
+        &self,
+        Parameters(args): Parameters<ShowMessage>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ShowMessage(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the Studio viewport camera position, look vector, CFrame components and field of view"
+    )]
+    async fn get_camera(
+        &self,
+        Parameters(args): Parameters<GetCamera>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetCamera(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Moves the Studio viewport camera, either to a position and look-at target or to a full CFrame. Returns the resulting camera CFrame"
+    )]
+    async fn set_camera(
+        &self,
+        Parameters(args): Parameters<SetCamera>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetCamera(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Frames the Studio camera on a Model or BasePart, like pressing F in Studio. Returns the computed camera position"
+    )]
+    async fn focus_instance(
+        &self,
+        Parameters(args): Parameters<FocusInstance>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::FocusInstance(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Inserts the contents of a local .rbxm/.rbxmx file into the place. Returns the paths of the inserted instances"
+    )]
+    async fn insert_local_model(
+        &self,
+        Parameters(args): Parameters<InsertLocalModel>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let path = match self.config.resolve_file_path(&args.file_path).await {
+            Ok(path) => path,
+            Err(err) => return Ok(err.into()),
+        };
+        let data = match read_local_model(&path).await {
+            Ok(data) => data,
+            Err(err) => return Ok(err.into()),
+        };
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| args.file_path.clone());
+        self.generic_tool_run(
+            ToolArgumentValues::InsertLocalModel(InsertLocalModelData {
+                file_name,
+                data,
+                parent_path: args.parent_path,
+            }),
+            context,
+        )
+        .await
+    }
+
+    #[tool(
+        description = "Exports an instance and its descendants to a local .rbxm file. Returns the written file size in bytes"
+    )]
+    async fn export_model(
+        &self,
+        Parameters(args): Parameters<ExportModel>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let file_path = args.file_path.clone();
+        let path = match self.config.resolve_file_path(&file_path).await {
+            Ok(path) => path,
+            Err(err) => return Ok(err.into()),
+        };
+        let exported = match self
+            .run_command(ToolArgumentValues::ExportModel(args), &context)
+            .await
+        {
+            Ok(exported) => exported.into_text(),
+            Err(err) => return Ok(self.reply(Err(err))),
+        };
+        match write_exported_model(&path, &exported).await {
+            Ok(size) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Exported {size} bytes to {file_path}"
+            ))])),
+            Err(err) => Ok(err.into()),
+        }
+    }
+
+    #[tool(
+        description = "Does a full round-trip to the Studio plugin and returns the measured latency in milliseconds. Also useful as a liveness check"
+    )]
+    async fn ping(
+        &self,
+        Parameters(args): Parameters<Ping>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let started = Instant::now();
+        match self
+            .run_command(ToolArgumentValues::Ping(args), &context)
+            .await
+        {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Round-trip to Studio took {} ms",
+                started.elapsed().as_millis()
+            ))])),
+            Err(err) => Ok(self.reply(Err(err))),
+        }
+    }
+
+    #[tool(
+        description = "Lists services already loaded in the DataModel, plus common services that can still be fetched with game:GetService"
+    )]
+    async fn list_services(
+        &self,
+        Parameters(args): Parameters<ListServices>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ListServices(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the Roblox Studio engine version, useful for deciding which APIs are available"
+    )]
+    async fn get_studio_version(
+        &self,
+        Parameters(args): Parameters<GetStudioVersion>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetStudioVersion(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns Workspace physics settings: gravity, air density, global wind and fallen parts destroy height"
+    )]
+    async fn get_physics_settings(
+        &self,
+        Parameters(args): Parameters<GetPhysicsSettings>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetPhysicsSettings(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Changes Workspace physics settings. Only the provided fields are applied, returns the resulting settings"
+    )]
+    async fn set_physics_settings(
+        &self,
+        Parameters(args): Parameters<SetPhysicsSettings>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetPhysicsSettings(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Counts the descendants of an instance and its direct children. Use it before get_project_structure to pick a traversal depth. Returns {count, children, capped}, where capped is true if counting stopped at max"
+    )]
+    async fn count_descendants(
+        &self,
+        Parameters(args): Parameters<CountDescendants>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CountDescendants(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Polls a Luau predicate in Studio until it is truthy or the timeout expires, useful for waiting on content that loads asynchronously. Returns {satisfied, elapsed_ms} plus the last error raised by the predicate, if any"
+    )]
+    async fn wait_for(
+        &self,
+        Parameters(args): Parameters<WaitFor>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::WaitFor(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Temporarily highlights instances in the Studio viewport so the user can see what is being referred to. Highlights are not saved with the place and the selection is left alone. Returns a handle per highlighted instance for clear_highlights"
+    )]
+    async fn highlight_instances(
+        &self,
+        Parameters(args): Parameters<HighlightInstances>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::HighlightInstances(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Removes highlights created by highlight_instances. Returns the number of highlights removed"
+    )]
+    async fn clear_highlights(
+        &self,
+        Parameters(args): Parameters<ClearHighlights>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ClearHighlights(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Finds the real instance paths that best match a possibly misspelled or miscased path, without changing anything. Returns up to 5 matches with a confidence of exact, case_insensitive, normalized or descendant (a level of the path was skipped)"
+    )]
+    async fn resolve_path(
+        &self,
+        Parameters(args): Parameters<ResolvePath>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ResolvePath(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets several properties of one instance in a single call and a single undo waypoint. Every property is attempted, the result lists which were applied and why the others failed"
+    )]
+    async fn set_properties(
+        &self,
+        Parameters(args): Parameters<SetProperties>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetProperties(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists the scriptable properties, methods and events of a Roblox class, including inherited ones. Use it to check property names before calling set_properties"
+    )]
+    async fn get_class_members(
+        &self,
+        Parameters(args): Parameters<GetClassMembers>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let class_name = args.class_name.clone();
+        // The whole reply is cached, each reply is truncated on its own.
+        if let Some(members) = self.class_members_cache.lock().await.get(&class_name) {
+            return Ok(self.reply(Ok(ToolOutput::Text(members.clone()))));
+        }
+        match self
+            .run_command(ToolArgumentValues::GetClassMembers(args), &context)
+            .await
+        {
+            Ok(members) => {
+                let members = members.into_text();
+                self.class_members_cache
+                    .lock()
+                    .await
+                    .insert(class_name, members.clone());
+                Ok(self.reply(Ok(ToolOutput::Text(members))))
+            }
+            Err(err) => Ok(self.reply(Err(err))),
+        }
+    }
+
+    #[tool(
+        description = "Cancels every queued and running tool call, which then fail with a cancelled error. Commands already running in Studio are not interrupted, only their results are discarded"
+    )]
+    async fn cancel_all(
+        &self,
+        Parameters(_): Parameters<CancelAll>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let outstanding = self.state.take_outstanding().await;
+        let cancelled = fail_callers(
+            outstanding,
+            ToolError::new(ToolErrorCode::Cancelled, "Cancelled by user"),
+        );
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Cancelled {cancelled} commands"
+        ))]))
+    }
+
+    #[tool(
+        description = "Returns place level settings: Workspace streaming settings, Lighting.Technology and SoundService.RespectFilteringEnabled"
+    )]
+    async fn get_place_config(
+        &self,
+        Parameters(args): Parameters<GetPlaceConfig>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetPlaceConfig(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Changes place level settings such as streaming and the lighting technology. Only the provided fields are applied, returns the resulting config"
+    )]
+    async fn set_place_config(
+        &self,
+        Parameters(args): Parameters<SetPlaceConfig>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetPlaceConfig(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Stores a string, number or boolean under a key in a scratchpad kept in the place (attributes of ServerStorage.MCPStore). Useful to remember state across tool calls. Returns the stored value and its type"
+    )]
+    async fn kv_set(
+        &self,
+        Parameters(args): Parameters<KvSet>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::KvSet(args), context)
+            .await
+    }
+
+    #[tool(description = "Reads a value stored with kv_set. Returns {key, found, value, type}")]
+    async fn kv_get(
+        &self,
+        Parameters(args): Parameters<KvGet>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::KvGet(args), context)
+            .await
+    }
+
+    #[tool(description = "Lists every key stored with kv_set together with its value and type")]
+    async fn kv_list(
+        &self,
+        Parameters(args): Parameters<KvList>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::KvList(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists the most recent undo waypoints committed for tool calls that may have changed the place in this Studio session, newest first. Each entry has the waypoint label (naming the tool) and a Unix timestamp"
+    )]
+    async fn get_history(
+        &self,
+        Parameters(args): Parameters<GetHistory>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetHistory(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Joins two BaseParts with a Weld, WeldConstraint or Motor6D that keeps their current relative position. Returns the path of the created joint"
+    )]
+    async fn weld_parts(
+        &self,
+        Parameters(args): Parameters<WeldParts>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::WeldParts(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Computes the world-space bounding box of an instance or the current selection. Returns the oriented box (cframe components and size) and the axis-aligned box (center, size, min and max)"
+    )]
+    async fn get_bounding_box(
+        &self,
+        Parameters(args): Parameters<GetBoundingBox>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetBoundingBox(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Compares two instance trees and returns only what differs: changed common properties and attributes as {path, property, a, b}, plus children present on one side only. Children are matched by name. Output is capped at 200 differences"
+    )]
+    async fn diff_instances(
+        &self,
+        Parameters(args): Parameters<DiffInstances>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::DiffInstances(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets one property on every instance currently selected in Studio, in a single undo waypoint. Returns how many instances were changed and which failed with why"
+    )]
+    async fn set_selection_property(
+        &self,
+        Parameters(args): Parameters<SetSelectionProperty>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetSelectionProperty(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Reads a key from a DataStore of the published experience, returning the stored JSON value and its version. Requires Studio access to API services"
+    )]
+    async fn data_store_get(
+        &self,
+        Parameters(args): Parameters<DataStoreGet>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::DataStoreGet(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists the DataStores of the published experience, or the keys of one DataStore when store_name is given. Requires Studio access to API services"
+    )]
+    async fn data_store_list(
+        &self,
+        Parameters(args): Parameters<DataStoreList>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::DataStoreList(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Writes a JSON value to a key of a DataStore of the published experience. This changes live game data and can't be undone. Unavailable when the server runs with --read-only"
+    )]
+    async fn data_store_set(
+        &self,
+        Parameters(args): Parameters<DataStoreSet>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if self.config.read_only {
+            return Ok(ToolError::new(
+                ToolErrorCode::Disabled,
+                "data_store_set is disabled because the server runs with --read-only",
+            )
+            .into());
+        }
+        self.generic_tool_run(ToolArgumentValues::DataStoreSet(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists the actions the MCP plugin registers with Studio, with their ids and descriptions. Users can bind keyboard shortcuts to them and trigger_action fires them"
+    )]
+    async fn list_actions(
+        &self,
+        Parameters(args): Parameters<ListActions>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ListActions(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Fires an action registered by the MCP plugin, such as grouping the selection. Returns whether the action existed and fired"
+    )]
+    async fn trigger_action(
+        &self,
+        Parameters(args): Parameters<TriggerAction>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::TriggerAction(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Injects a click, mouse move or key press into the Studio viewport for testing GUIs. Only works where Studio lets plugins use VirtualInputManager, usually while play testing with the viewport focused, and fails with a descriptive error otherwise"
+    )]
+    async fn simulate_input(
+        &self,
+        Parameters(args): Parameters<SimulateInput>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SimulateInput(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns which editing tool (Select, Move, Scale, Rotate, Transform) is active in Studio"
+    )]
+    async fn get_edit_mode(
+        &self,
+        Parameters(args): Parameters<GetEditMode>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetEditMode(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Activates an editing tool (Select, Move, Scale, Rotate, Transform) in Studio, showing its gizmo on the selection. Returns the now active mode"
+    )]
+    async fn set_edit_mode(
+        &self,
+        Parameters(args): Parameters<SetEditMode>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetEditMode(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Searches the Roblox marketplace for free models without inserting anything. Returns candidates with their name, asset id and creator so one can be picked for insert_model_by_asset_id"
+    )]
+    async fn search_marketplace(
+        &self,
+        Parameters(args): Parameters<SearchMarketplace>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SearchMarketplace(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Inserts a marketplace model by asset id into the workspace or under parent_path. Returns the full path of the inserted model"
+    )]
+    async fn insert_model_by_asset_id(
+        &self,
+        Parameters(args): Parameters<InsertModelByAssetId>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::InsertModelByAssetId(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Registers a new collision group with PhysicsService. Returns every registered group and which groups it collides with"
+    )]
+    async fn create_collision_group(
+        &self,
+        Parameters(args): Parameters<CreateCollisionGroup>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateCollisionGroup(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Assigns a BasePart, or every BasePart inside a model or folder, to a registered collision group. Returns the parts that are now members"
+    )]
+    async fn set_collision_group(
+        &self,
+        Parameters(args): Parameters<SetCollisionGroup>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetCollisionGroup(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets whether two registered collision groups collide with each other. Returns every registered group and which groups it collides with"
+    )]
+    async fn set_collision_group_collidable(
+        &self,
+        Parameters(args): Parameters<SetCollisionGroupCollidable>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(
+            ToolArgumentValues::SetCollisionGroupCollidable(args),
+            context,
+        )
+        .await
+    }
+
+    #[tool(
+        description = "Applies a material, and optionally a color, to every BasePart under root_path matching a name pattern and/or class in a single undo waypoint. Returns how many parts matched and were changed, use dry_run to only count them"
+    )]
+    async fn apply_material(
+        &self,
+        Parameters(args): Parameters<ApplyMaterial>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ApplyMaterial(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Reports whether the place has been modified, returning modified, modification_count (undoable changes, undos and redos seen), can_undo and tracked_since. Studio doesn't expose its save state, so changes are counted from when the plugin loaded and a save made since then isn't noticed. Fast and read-only, meant to be checked before risky operations so the user can be asked to save first"
+    )]
+    async fn get_dirty_state(
+        &self,
+        Parameters(args): Parameters<GetDirtyState>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetDirtyState(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Requires a ModuleScript in a protected call and describes what it returned: its type, its value for primitives, and for tables the top-level keys with the type of each. Each module is required at most once per plugin session (cached is true for later calls), so its side effects don't run again and edits to it since aren't picked up. Require errors are returned with a traceback"
+    )]
+    async fn require_module(
+        &self,
+        Parameters(args): Parameters<RequireModule>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::RequireModule(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Creates a tree of GUI objects from a spec, e.g. a ScreenGui holding Frames, labels, buttons and layout objects, and returns the path of the root and how many instances were created. Sizes and positions are UDim2 components (x_scale, x_offset, y_scale, y_offset). Build a layout over a few calls by passing an existing GUI object as parent_path. At most 200 instances per call"
+    )]
+    async fn create_gui(
+        &self,
+        Parameters(args): Parameters<CreateGui>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateGui(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Finds sets of sibling instances under root_path sharing both name and class, which usually means something was pasted or inserted twice. Returns each set with its paths. Only reports by default, call again with dry_run false after confirming to delete all but the first instance of each set"
+    )]
+    async fn find_duplicates(
+        &self,
+        Parameters(args): Parameters<FindDuplicates>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::FindDuplicates(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Finds Folders and Models under root_path that are empty, or only hold other empty Folders and Models. Returns how many were found and their paths. Only reports by default, call again with dry_run false after confirming to delete them"
+    )]
+    async fn remove_empty(
+        &self,
+        Parameters(args): Parameters<RemoveEmpty>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::RemoveEmpty(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the place and game ids, the place's creator, the id of the signed in Studio user, and whether Team Create is active. When team_create is true other people may be editing the same place and see every change, so warn the user and confirm before destructive or sweeping edits. place_id and game_id are 0 for places that were never published"
+    )]
+    async fn get_session_info(
+        &self,
+        Parameters(args): Parameters<GetSessionInfo>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetSessionInfo(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Creates a grid of identical parts inside a new Model, counts.x by counts.y by counts.z parts spaced evenly from position. Returns the Model path and how many parts were created. Grids of more than 1000 parts are rejected"
+    )]
+    async fn create_grid(
+        &self,
+        Parameters(args): Parameters<CreateGrid>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateGrid(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Enables or disables a Script or LocalScript, or with recursive every script under a container, returning how many scripts matched and changed. Disabling is a reversible way to isolate a misbehaving script, prefer it over deleting scripts while debugging"
+    )]
+    async fn set_script_enabled(
+        &self,
+        Parameters(args): Parameters<SetScriptEnabled>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetScriptEnabled(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Counts the errors and warnings in Studio's buffered Output and returns the most recent error message, without pulling the whole log. Pass the latest_timestamp of a previous call as since to only count what was logged afterwards, e.g. to check whether running code broke anything"
+    )]
+    async fn get_diagnostics_summary(
+        &self,
+        Parameters(args): Parameters<GetDiagnosticsSummary>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetDiagnosticsSummary(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets any of transparency, reflectance, material, color and cast_shadow on a BasePart in one call, leaving omitted fields unchanged. Returns the resulting value of all five. Prefer this over set_properties for these common appearance fields"
+    )]
+    async fn set_appearance(
+        &self,
+        Parameters(args): Parameters<SetAppearance>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetAppearance(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Records the current property values of up to 200 instances under a name, so they can be put back with restore_snapshot after experimenting, independently of Studio's undo history. Snapshots live in the plugin, at most 10 are kept and each expires after an hour"
+    )]
+    async fn snapshot_properties(
+        &self,
+        Parameters(args): Parameters<SnapshotProperties>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SnapshotProperties(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets the properties recorded by snapshot_properties back to their recorded values and returns every property that changed with its value before and after. Instances deleted since the snapshot are listed as missing. The snapshot is kept, so it can be restored again"
+    )]
+    async fn restore_snapshot(
+        &self,
+        Parameters(args): Parameters<RestoreSnapshot>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::RestoreSnapshot(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists what can be seen of the plugins loaded in Studio, to confirm the MCP plugin is loaded or spot plugins that may conflict with it. Studio only lets plugins enumerate each other in some setups: when enumerable is false plugins is omitted and only widgets, the windows plugins have opened with their titles, is available"
+    )]
+    async fn list_installed_plugins(
+        &self,
+        Parameters(args): Parameters<ListInstalledPlugins>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ListInstalledPlugins(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Projects world positions through the current Studio camera onto the viewport, returning pixel x and y usable with simulate_input, the depth in studs and whether each point is on screen. Points behind the camera have behind_camera set and no x or y, since they have no place on screen"
+    )]
+    async fn world_to_screen(
+        &self,
+        Parameters(args): Parameters<WorldToScreen>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::WorldToScreen(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Casts a ray from the current Studio camera through a viewport pixel and returns the ray's origin and direction along with the first part it hits, if any, with the hit position, surface normal, material and distance. Useful to find what is under a point before clicking it with simulate_input"
+    )]
+    async fn screen_to_world(
+        &self,
+        Parameters(args): Parameters<ScreenToWorld>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ScreenToWorld(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the linear and angular velocity of the assembly a part belongs to, the parts welded to it that move together, along with the assembly's root part, mass and whether it is anchored"
+    )]
+    async fn get_velocity(
+        &self,
+        Parameters(args): Parameters<GetVelocity>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetVelocity(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets the linear and/or angular velocity of the assembly a part belongs to through its root part, which is what physics reads. Anchored assemblies are rejected. Returns the resulting velocities. Physics only runs while play testing or running, so the assembly doesn't move in edit mode"
+    )]
+    async fn set_velocity(
+        &self,
+        Parameters(args): Parameters<SetVelocity>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetVelocity(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Applies a linear impulse, optionally at a world position, and/or an angular impulse to the assembly a part belongs to, changing its velocity in proportion to its mass. Anchored assemblies are rejected. Returns the resulting velocities"
+    )]
+    async fn apply_impulse(
+        &self,
+        Parameters(args): Parameters<ApplyImpulse>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ApplyImpulse(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists the scripts open in the Studio script editor with their paths, cursor position and selected text, and which one is active, the one the user is looking at. Prefer the active script when the user says this script without naming one"
+    )]
+    async fn get_open_documents(
+        &self,
+        Parameters(args): Parameters<GetOpenDocuments>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetOpenDocuments(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Runs Luau assertions in Studio and reports whether they passed, for verifying an edit had the intended effect. Each assertion runs protected, so a failing one doesn't stop the others, and fails by raising an error (e.g. with assert) or returning false. Returns passed, the number of failed and skipped assertions and per assertion results with the error message of each failure"
+    )]
+    async fn run_test(
+        &self,
+        Parameters(args): Parameters<RunTest>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::RunTest(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the path of a Model's PrimaryPart, the part its pivot and PivotTo movement are based on. set is false and primary_part omitted when the model has none"
+    )]
+    async fn get_primary_part(
+        &self,
+        Parameters(args): Parameters<GetPrimaryPart>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetPrimaryPart(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets a Model's PrimaryPart to one of its descendant BaseParts, so the model can be moved reliably with PivotTo. Parts outside the model are rejected. Returns the model's new PrimaryPart"
+    )]
+    async fn set_primary_part(
+        &self,
+        Parameters(args): Parameters<SetPrimaryPart>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetPrimaryPart(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns performance figures from Studio's Stats service: instance and part counts, physics contacts, heartbeat, physics step and render frame times in milliseconds, and memory use in megabytes in total and per category. Stats Studio doesn't let plugins read are omitted. Useful to notice when a place is getting too heavy"
+    )]
+    async fn get_performance_stats(
+        &self,
+        Parameters(args): Parameters<GetPerformanceStats>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetPerformanceStats(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Creates a ParticleEmitter under a part or attachment, setting only the given properties: rate, lifetime, speed, size, color, texture, transparency and light emission. Single values apply over each particle's whole life. Returns the new emitter's path and the properties that were set"
+    )]
+    async fn create_particle_emitter(
+        &self,
+        Parameters(args): Parameters<CreateParticleEmitter>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateParticleEmitter(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Creates a Beam drawn between two Attachments, setting only the given properties: width, color, texture, transparency and light emission. Returns the new beam's path and the properties that were set"
+    )]
+    async fn create_beam(
+        &self,
+        Parameters(args): Parameters<CreateBeam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateBeam(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Creates a Trail drawn between two Attachments as they move, setting only the given properties: lifetime, color, texture, transparency and light emission. Returns the new trail's path and the properties that were set"
+    )]
+    async fn create_trail(
+        &self,
+        Parameters(args): Parameters<CreateTrail>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateTrail(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns the Workspace toggles commonly changed while testing: instance streaming (enabled, min and target radius, integrity mode), the physics stepping method and whether touches respect collision groups. Enum values are returned as item names"
+    )]
+    async fn get_workspace_flags(
+        &self,
+        Parameters(args): Parameters<GetWorkspaceFlags>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetWorkspaceFlags(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Sets the given Workspace streaming and physics solver toggles, leaving omitted ones unchanged. Returns the resulting values of all of them, like get_workspace_flags"
+    )]
+    async fn set_workspace_flags(
+        &self,
+        Parameters(args): Parameters<SetWorkspaceFlags>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetWorkspaceFlags(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Lists models owned by the user signed in to Studio, from the sets shown under My Models in the toolbox, with their name and asset id so they can be inserted with insert_model_by_asset_id. Requires being signed in to Studio and fails with an error otherwise. Assets that were never added to one of the user's sets aren't listed"
+    )]
+    async fn list_my_assets(
+        &self,
+        Parameters(args): Parameters<ListMyAssets>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ListMyAssets(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Measures between two parts, models or attachments: the offset from a's pivot to b's, the distance between the pivots and between their centers (bounding box centers for models), and whether one contains the other (relationship: same, a_contains_b, b_contains_a or unrelated)"
+    )]
+    async fn measure(
+        &self,
+        Parameters(args): Parameters<Measure>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::InsertModel(args))
+        self.generic_tool_run(ToolArgumentValues::Measure(args), context)
             .await
     }
 
-    // BEGIN ADDITION
-    #[tool(description = "Deletes a part from the workspace by name")]
-    async fn delete_part(
+    #[tool(
+        description = "Sets the Studio grid snap and rotate snap increments, leaving omitted ones unchanged, and returns the resulting values of both. These are settings of the user's Studio rather than of the place: they aren't saved with the place, aren't undone with undo and apply to every place the user opens. Fields Studio refuses to change are listed under not_applied with the reason"
+    )]
+    async fn set_studio_settings(
         &self,
-        Parameters(args): Parameters<DeletePart>,
+        Parameters(args): Parameters<SetStudioSettings>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SetStudioSettings(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns a name no child of parent_path currently has: base_name itself when it is free, otherwise base_name followed by the lowest free number starting at 2, e.g. Part2. Use it before creating an instance so two siblings don't share a name, which makes paths to them ambiguous"
+    )]
+    async fn suggest_name(
+        &self,
+        Parameters(args): Parameters<SuggestName>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::SuggestName(args), context)
+            .await
+    }
+
+    #[tool(
+        description = "Returns whether the place is being edited or is running in Run mode (F8), as mode edit or run, along with whether scripts are executing. Changes made in Run mode are discarded when it stops, so tools that change the place are refused then unless the server was started with --allow-edits-while-running. Play sessions (F5) run in a separate copy of the place and report edit, changes made to the place during Play are kept"
+    )]
+    async fn get_run_state(
+        &self,
+        Parameters(args): Parameters<GetRunState>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::DeletePart(args))
+        self.generic_tool_run(ToolArgumentValues::GetRunState(args), context)
             .await
     }
 
-    #[tool(description = "Gets project structure with configurable detail level")]
-    async fn get_project_structure(&self, Parameters(args): Parameters<GetProjectStructure>) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::GetProjectStructure(args)).await
+    #[tool(
+        description = "Reads properties of many instances in one call. Returns results in the order of entries, each with the instance's full path and the values read, encoded like snapshot_properties. A path that doesn't resolve gives that entry an error instead, and properties that can't be read are listed under the entry's errors, so one bad entry doesn't fail the others"
+    )]
+    async fn get_properties_bulk(
+        &self,
+        Parameters(args): Parameters<GetPropertiesBulk>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetPropertiesBulk(args), context)
+            .await
     }
     // END ADDITION
 
     async fn generic_tool_run(
         &self,
         args: ToolArgumentValues,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        Ok(self.reply(self.run_command(args, &context).await))
+    }
+
+    /// Turns the result of a command into the tool call result, truncating text that exceeds
+    /// `--max-response-bytes`.
+    fn reply(&self, result: ToolResult) -> CallToolResult {
+        let max = self.config.max_response_bytes;
+        match result {
+            Ok(ToolOutput::Text(mut text)) => {
+                truncate_response(&mut text, max);
+                CallToolResult::success(vec![Content::text(text)])
+            }
+            Ok(result) => CallToolResult::success(vec![result.into_content()]),
+            Err(mut err) => {
+                truncate_response(&mut err.message, max);
+                err.into()
+            }
+        }
+    }
+
+    /// Runs a script, or if an identical one was started less than the dedup window ago and is
+    /// still running, waits for that execution and returns its result instead.
+    async fn run_code_deduplicated(
+        &self,
+        args: RunCode,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
-        let (command, id) = ToolArguments::new(args);
-        tracing::debug!("Running command: {:?}", command);
-        let (tx, mut rx) = mpsc::unbounded_channel::<Result<String>>();
-        let trigger = {
-            let mut state = self.state.lock().await;
-            state.process_queue.push_back(command);
-            state.output_map.insert(id, tx);
-            state.trigger.clone()
+        let window = self.config.run_code_dedup_window;
+        if window.is_zero() {
+            return self
+                .generic_tool_run(ToolArgumentValues::RunCode(args), context)
+                .await;
+        }
+        let key = args.command.clone();
+        let (tx, rx) = watch::channel(None);
+        let shared = {
+            let mut in_flight_scripts = self.state.run_code_in_flight.lock().await;
+            match in_flight_scripts.get(&key) {
+                // A closed channel means the first caller was cancelled before it got a result.
+                Some(in_flight)
+                    if in_flight.started.elapsed() < window
+                        && in_flight.result.has_changed().is_ok() =>
+                {
+                    Some(in_flight.result.clone())
+                }
+                _ => {
+                    in_flight_scripts.insert(
+                        key.clone(),
+                        InFlightRunCode {
+                            started: Instant::now(),
+                            result: rx.clone(),
+                        },
+                    );
+                    None
+                }
+            }
         };
-        trigger
-            .send(())
-            .map_err(|e| ErrorData::internal_error(format!("Unable to trigger send {e}"), None))?;
-        let result = rx
-            .recv()
+        if let Some(mut shared) = shared {
+            tracing::debug!("Sharing the result of an identical run_code already in flight");
+            let result = shared
+                .wait_for(Option::is_some)
+                .await
+                .ok()
+                .and_then(|result| result.clone());
+            return result.unwrap_or_else(|| {
+                Ok(
+                    ToolError::internal("The identical run_code this call waited on was cancelled")
+                        .into(),
+                )
+            });
+        }
+
+        let result = self
+            .generic_tool_run(ToolArgumentValues::RunCode(args), context)
+            .await;
+        tx.send_replace(Some(result.clone()));
+        let mut in_flight_scripts = self.state.run_code_in_flight.lock().await;
+        if in_flight_scripts
+            .get(&key)
+            .is_some_and(|in_flight| in_flight.result.same_channel(&rx))
+        {
+            in_flight_scripts.remove(&key);
+        }
+        result
+    }
+
+    /// Queues a command for the plugin and waits for its raw reply.
+    async fn run_command(
+        &self,
+        args: ToolArgumentValues,
+        context: &RequestContext<RoleServer>,
+    ) -> ToolResult {
+        args.validate()?;
+        if !self
+            .rate_limiter
+            .lock()
             .await
-            .ok_or(ErrorData::internal_error("Couldn't receive response", None))?;
+            .try_acquire(std::mem::discriminant(&args))
         {
-            let mut state = self.state.lock().await;
-            state.output_map.remove_entry(&id);
+            return Err(ToolError::new(
+                ToolErrorCode::RateLimited,
+                "Rate limited, slow down: this tool is being called too often",
+            ));
         }
-        tracing::debug!("Sending to MCP: {result:?}");
-        match result {
-            Ok(result) => Ok(CallToolResult::success(vec![Content::text(result)])),
-            Err(err) => Ok(CallToolResult::error(vec![Content::text(err.to_string())])),
+        let reply_timeout = args.reply_timeout(self.config.tool_timeout);
+        let logs = match &args {
+            ToolArgumentValues::RunCode(RunCode {
+                stream_logs: Some(true),
+                ..
+            }) => Some(LogForwarder {
+                peer: context.peer.clone(),
+                min_level: *self.log_level.lock().await,
+            }),
+            _ => None,
+        };
+        let (mut command, id) = ToolArguments::new(args, reply_timeout);
+        command.refuse_while_running =
+            !self.config.allow_edits_while_running && command.changes_place;
+        tracing::Span::current().record("id", tracing::field::display(id));
+        tracing::debug!(
+            "Running command: {:?}",
+            LoggedCommand {
+                command: &command,
+                redact: self.config.redact_logs,
+            }
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel::<CommandReply>();
+        let progress = ProgressReporter::from_context(context);
+        let state = &self.state;
+        if state.shutting_down.load(Ordering::Relaxed) {
+            return Err(ToolError::internal("Server is shutting down"));
         }
+        // A proxying instance never sees the plugin, the primary checks on its behalf.
+        if self.config.no_wait && !state.proxying.load(Ordering::Relaxed) {
+            state.require_plugin().await?;
+        }
+        if state.queued_commands().await >= MAX_QUEUED_COMMANDS {
+            return Err(ToolError::new(
+                ToolErrorCode::QueueFull,
+                format!("{MAX_QUEUED_COMMANDS} commands are already waiting for Studio, is the plugin connected?"),
+            ));
+        }
+        let notifier = progress.map(|progress| {
+            let (dispatched_tx, dispatched_rx) = oneshot::channel();
+            tokio::spawn(progress.report_dispatch(dispatched_rx));
+            dispatched_tx
+        });
+        if let Some(journal) = &mut *state.journal.lock().await {
+            journal.queued(id, &command);
+        }
+        state.push_command(command, tx, notifier).await;
+        let result = tokio::time::timeout(reply_timeout, collect_reply(&mut rx, logs.as_ref()))
+            .await
+            .unwrap_or_else(|_| {
+                Err(ToolError::new(
+                    ToolErrorCode::Timeout,
+                    format!(
+                        "Studio didn't reply within {} seconds",
+                        reply_timeout.as_secs()
+                    ),
+                ))
+            });
+        state.remove_command(id).await;
+        tracing::debug!("Sending to MCP: {result:?}");
+        let outcome = match &result {
+            Ok(_) => "ok",
+            Err(err) => err.code.name(),
+        };
+        tracing::Span::current().record("outcome", outcome);
+        result
+    }
+}
+
+/// 64-bit FNV-1a hash of `text` as hex. Stable across runs and builds, unlike `DefaultHasher`,
+/// so etags stay valid when the server restarts.
+fn content_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Reply of get_project_structure, a short not modified reply if the client already has this
+/// `structure`, otherwise the structure followed by its etag.
+fn etag_reply(mut structure: String, if_none_match: Option<&str>, max: usize) -> CallToolResult {
+    // Hashed before truncation so the etag identifies the whole tree.
+    let etag = content_hash(&structure);
+    if if_none_match == Some(etag.as_str()) {
+        return CallToolResult::success(vec![Content::text(format!("Not modified, etag: {etag}"))]);
+    }
+    truncate_response(&mut structure, max);
+    CallToolResult::success(vec![
+        Content::text(structure),
+        Content::text(format!("etag: {etag}")),
+    ])
+}
+
+/// Cuts `text` down to at most `max` bytes plus a marker saying how much was dropped, so a
+/// runaway script can't produce a reply the MCP client refuses.
+fn truncate_response(text: &mut String, max: usize) {
+    if max == 0 || text.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let truncated = text.len() - end;
+    text.truncate(end);
+    text.push_str(&format!("...[truncated {truncated} bytes]"));
+}
+
+async fn read_local_model(path: &Path) -> Result<String, ToolError> {
+    let metadata = tokio::fs::metadata(path).await.map_err(|e| {
+        ToolError::invalid_arguments(format!(
+            "Can't access {} from the MCP server process: {e}",
+            path.display()
+        ))
+    })?;
+    if !metadata.is_file() {
+        return Err(ToolError::invalid_arguments(format!(
+            "{} is not a file",
+            path.display()
+        )));
     }
+    if metadata.len() > MAX_LOCAL_MODEL_BYTES {
+        return Err(ToolError::invalid_arguments(format!(
+            "{} is {} bytes, which exceeds the {MAX_LOCAL_MODEL_BYTES} byte limit",
+            path.display(),
+            metadata.len()
+        )));
+    }
+    let bytes = tokio::fs::read(path).await.map_err(|e| {
+        ToolError::invalid_arguments(format!("Failed to read {}: {e}", path.display()))
+    })?;
+    Ok(BASE64.encode(bytes))
+}
+
+async fn write_exported_model(path: &Path, exported: &str) -> Result<usize, ToolError> {
+    let bytes = BASE64
+        .decode(exported)
+        .map_err(|e| ToolError::internal(format!("Plugin returned invalid model data: {e}")))?;
+    tokio::fs::write(path, &bytes).await.map_err(|e| {
+        ToolError::invalid_arguments(format!("Failed to write {}: {e}", path.display()))
+    })?;
+    Ok(bytes.len())
+}
+
+/// Routes the plugin, proxying instances and operators talk to on the plugin port.
+pub fn plugin_router(state: PackedState) -> axum::Router {
+    axum::Router::new()
+        .route("/request", get(request_handler))
+        .route("/response", post(response_handler))
+        .route("/proxy", post(proxy_handler))
+        .route("/health", get(health_handler))
+        .route("/heartbeat", post(heartbeat_handler))
+        .route("/log", post(log_handler))
+        .route("/cancel-all", post(cancel_all_handler))
+        .layer(DefaultBodyLimit::max(MAX_PLUGIN_BODY_BYTES))
+        .with_state(state)
 }
 
-pub async fn request_handler(State(state): State<PackedState>) -> Result<impl IntoResponse> {
+/// Long poll the plugin uses to pick up commands. Polls with no command to hand out within
+/// `LONG_POLL_DURATION` get a 423 LOCKED reply, which means to poll again right away, whereas
+/// failing to connect means the server isn't up and the plugin backs off before retrying.
+#[tracing::instrument(skip_all, fields(id = tracing::field::Empty))]
+pub async fn request_handler(
+    State(state): State<PackedState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse> {
+    if let Some(version) = headers
+        .get(STUDIO_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        *state.studio_version.lock().await = Some(version.to_string());
+    }
+    // The first poll is the plugin's handshake, it counts as connected before its first
+    // heartbeat arrives.
+    state.plugin_seen().await;
     let timeout = tokio::time::timeout(LONG_POLL_DURATION, async {
         loop {
-            let mut waiter = {
-                let mut state = state.lock().await;
-                if let Some(task) = state.process_queue.pop_front() {
-                    return Ok::<ToolArguments, Error>(task);
-                }
-                state.waiter.clone()
-            };
+            // Subscribed before looking at the queue so a command queued in between still
+            // wakes this poll up.
+            let mut waiter = state.waiter.clone();
+            if let Some(task) = state.pop_command().await {
+                return Ok::<_, Error>(task);
+            }
             waiter.changed().await?
         }
     })
     .await;
     match timeout {
-        Ok(result) => Ok(Json(result?).into_response()),
+        Ok(result) => {
+            let (command, notifier) = result?;
+            notify_dispatched(notifier);
+            if let Some(id) = command.id {
+                tracing::Span::current().record("id", tracing::field::display(id));
+            }
+            tracing::debug!("Dispatching command to Studio");
+            Ok(Json(command).into_response())
+        }
         _ => Ok((StatusCode::LOCKED, String::new()).into_response()),
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct HealthResponse {
+    status: String,
+    server_version: String,
+    studio_version: Option<String>,
+    #[serde(default)]
+    plugin_connected: bool,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn health_handler(State(state): State<PackedState>) -> impl IntoResponse {
+    Json(HealthResponse {
+        status: "ok".to_string(),
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        studio_version: state.studio_version.lock().await.clone(),
+        plugin_connected: state.plugin_connected().await,
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HeartbeatResponse {
+    /// When the plugin should send its next heartbeat.
+    pub interval_ms: u64,
+}
+
+/// Posted by the plugin every `HEARTBEAT_INTERVAL` while it is connected.
+#[tracing::instrument(skip_all)]
+pub async fn heartbeat_handler(State(state): State<PackedState>) -> impl IntoResponse {
+    state.plugin_seen().await;
+    Json(HeartbeatResponse {
+        interval_ms: HEARTBEAT_INTERVAL.as_millis() as u64,
+    })
+}
+
+/// Checks that the process holding the plugin port is another instance of this server before
+/// commands are proxied to it, so an unrelated application on the port is reported up front.
+pub async fn probe_primary() -> color_eyre::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(HEALTH_PROBE_TIMEOUT)
+        .build()?;
+    let url = format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/health");
+    let mut attempt = 1;
+    let res = loop {
+        match client.get(&url).send().await {
+            // The primary may have bound the port but not be serving yet.
+            Err(e) if e.is_connect() && attempt < PROXY_CONNECT_ATTEMPTS => {
+                attempt += 1;
+                tokio::time::sleep(PROXY_RETRY_DELAY).await;
+            }
+            res => break res,
+        }
+    };
+    let health = match res.and_then(reqwest::Response::error_for_status) {
+        Ok(res) => res.json::<HealthResponse>().await.ok(),
+        Err(e) => {
+            tracing::debug!("Health probe failed: {e}");
+            None
+        }
+    };
+    match health {
+        Some(health) => {
+            tracing::info!(
+                "Port {STUDIO_PLUGIN_PORT} is held by MCP server {}",
+                health.server_version
+            );
+            Ok(())
+        }
+        None => color_eyre::eyre::bail!(
+            "Port {STUDIO_PLUGIN_PORT} is in use by an application that isn't the Roblox Studio MCP server. Close it and try again"
+        ),
+    }
+}
+
+#[tracing::instrument(skip_all, fields(id = %payload.id))]
 pub async fn response_handler(
     State(state): State<PackedState>,
     Json(payload): Json<RunCommandResponse>,
-) -> Result<impl IntoResponse> {
+) -> StatusCode {
     tracing::debug!("Received reply from studio {payload:?}");
-    let mut state = state.lock().await;
-    let tx = state
-        .output_map
-        .remove(&payload.id)
-        .ok_or_eyre("Unknown ID")?;
-    Ok(tx.send(Ok(payload.response))?)
+    let tx = if payload.partial {
+        state.output_map.lock().await.get(&payload.id).cloned()
+    } else {
+        state.take_caller(payload.id).await
+    };
+    // A plugin that outlived a server restart can still reply to commands of the previous
+    // server, and callers may give up before the reply arrives. Neither is the plugin's fault.
+    let Some(tx) = tx else {
+        tracing::warn!("Ignoring reply to a command this server doesn't know");
+        return StatusCode::OK;
+    };
+    let reply = if payload.partial {
+        CommandReply::Chunk(payload.response)
+    } else {
+        CommandReply::Done(payload.into_result())
+    };
+    if tx.send(reply).is_err() {
+        tracing::debug!("Dropping reply, the caller stopped waiting for it");
+    }
+    StatusCode::OK
+}
+
+/// Studio log lines the plugin posts while a `run_code` with `stream_logs` runs.
+#[derive(Debug, Deserialize)]
+pub struct LogChunk {
+    id: Uuid,
+    messages: Vec<LogLine>,
+}
+
+#[tracing::instrument(skip_all, fields(id = %chunk.id))]
+pub async fn log_handler(
+    State(state): State<PackedState>,
+    Json(chunk): Json<LogChunk>,
+) -> StatusCode {
+    let tx = state.output_map.lock().await.get(&chunk.id).cloned();
+    // Lines can trail the final reply, by then there is no one left to forward them to.
+    match tx {
+        Some(tx) => {
+            tx.send(CommandReply::Log(chunk.messages)).ok();
+        }
+        None => tracing::debug!("Dropping log lines of a command this server doesn't know"),
+    }
+    StatusCode::OK
+}
+
+/// Kill switch for operators: fails everything that is queued or waiting on Studio.
+#[tracing::instrument(skip_all)]
+pub async fn cancel_all_handler(State(state): State<PackedState>) -> impl IntoResponse {
+    let outstanding = state.take_outstanding().await;
+    let cancelled = fail_callers(
+        outstanding,
+        ToolError::new(ToolErrorCode::Cancelled, "Cancelled by user"),
+    );
+    tracing::info!("Cancelled {cancelled} commands");
+    Json(serde_json::json!({ "cancelled": cancelled }))
+}
+
+#[derive(Deserialize)]
+pub struct ProxyParams {
+    /// Fail the command right away if the plugin isn't connected, see `--no-wait`.
+    #[serde(default)]
+    no_wait: bool,
 }
 
+/// Body of a 400 reply to a proxied command that can never succeed, so the proxying instance
+/// fails it instead of treating the reply as a transient error.
+#[derive(Serialize, Deserialize)]
+struct ProxyRejection {
+    error: String,
+}
+
+fn reject_proxy_command(error: String) -> axum::response::Response {
+    tracing::error!("Rejecting proxied command: {error}");
+    (StatusCode::BAD_REQUEST, Json(ProxyRejection { error })).into_response()
+}
+
+#[tracing::instrument(skip_all, fields(id = tracing::field::Empty))]
 pub async fn proxy_handler(
     State(state): State<PackedState>,
-    Json(command): Json<ToolArguments>,
-) -> Result<impl IntoResponse> {
-    let id = command.id.ok_or_eyre("Got proxy command with no id")?;
-    tracing::debug!("Received request to proxy {command:?}");
+    Query(params): Query<ProxyParams>,
+    command: Result<Json<ToolArguments>, JsonRejection>,
+) -> axum::response::Response {
+    let command = match command {
+        Ok(Json(command)) => command,
+        Err(rejection) => {
+            // Usually a tool the two instances disagree on because they run different versions.
+            return reject_proxy_command(format!("Invalid command: {}", rejection.body_text()));
+        }
+    };
+    let Some(id) = command.id else {
+        return reject_proxy_command("Command has no id".to_string());
+    };
+    tracing::Span::current().record("id", tracing::field::display(id));
+    // Whether the secondary redacts its logs isn't known here, so scripts always are.
+    let logged = LoggedCommand {
+        command: &command,
+        redact: true,
+    };
+    tracing::debug!("Received request to proxy {logged:?}");
     let (tx, mut rx) = mpsc::unbounded_channel();
-    {
-        let mut state = state.lock().await;
-        state.process_queue.push_back(command);
-        state.output_map.insert(id, tx);
-    }
-    let response = rx.recv().await.ok_or_eyre("Couldn't receive response")??;
-    {
-        let mut state = state.lock().await;
-        state.output_map.remove_entry(&id);
+    if params.no_wait {
+        if let Err(err) = state.require_plugin().await {
+            return Json(RunCommandResponse::from_result(id, Err(err))).into_response();
+        }
     }
+    state.push_command(command, tx, None).await;
+    // Log lines don't make it across the proxy, only the reply does.
+    let response = collect_reply(&mut rx, None).await;
+    state.output_map.lock().await.remove(&id);
     tracing::debug!("Sending back to dud: {response:?}");
-    Ok(Json(RunCommandResponse { response, id }))
+    Json(RunCommandResponse::from_result(id, response)).into_response()
+}
+
+/// Posts a command to the primary instance at `primary`, retrying while it can't be connected to.
+async fn send_to_proxy(
+    client: &reqwest::Client,
+    primary: &str,
+    entry: &ToolArguments,
+    timeout: Duration,
+    no_wait: bool,
+) -> reqwest::Result<reqwest::Response> {
+    let mut attempt = 1;
+    loop {
+        let res = client
+            .post(format!("{primary}/proxy"))
+            .query(&[("no_wait", no_wait)])
+            .timeout(timeout)
+            .json(entry)
+            .send()
+            .await;
+        match res {
+            Err(e) if e.is_connect() && attempt < PROXY_CONNECT_ATTEMPTS => {
+                tracing::warn!("Failed to connect to proxy, retrying: {e}");
+                attempt += 1;
+                tokio::time::sleep(PROXY_RETRY_DELAY).await;
+            }
+            res => return res,
+        }
+    }
 }
 
-pub async fn dud_proxy_loop(state: PackedState, exit: Receiver<()>) {
-    let client = reqwest::Client::new();
+/// Forwards commands to the instance that owns the plugin port, reachable at `primary`.
+/// `tool_timeout` bounds how long each proxied command may take, on top of the time the primary
+/// needs to hand it to Studio. With `no_wait` the primary fails commands right away while the
+/// plugin isn't connected.
+pub async fn dud_proxy_loop(
+    state: PackedState,
+    mut exit: Receiver<()>,
+    primary: String,
+    tool_timeout: Duration,
+    no_wait: bool,
+) {
+    let client = reqwest::Client::builder()
+        .connect_timeout(LONG_POLL_DURATION)
+        .build()
+        .expect("Failed to build the proxy HTTP client");
 
-    let mut waiter = { state.lock().await.waiter.clone() };
+    let mut waiter = state.waiter.clone();
     while exit.is_empty() {
-        let entry = { state.lock().await.process_queue.pop_front() };
-        if let Some(entry) = entry {
-            let res = client
-                .post(format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/proxy"))
-                .json(&entry)
-                .send()
+        let entry = state.pop_command().await;
+        if let Some((entry, notifier)) = entry {
+            notify_dispatched(notifier);
+            let span = tracing::info_span!("proxy_command", id = ?entry.id);
+            proxy_command(&state, &client, &primary, entry, tool_timeout, no_wait)
+                .instrument(span)
                 .await;
-            if let Ok(res) = res {
-                let tx = {
-                    state
-                        .lock()
-                        .await
-                        .output_map
-                        .remove(&entry.id.unwrap())
-                        .unwrap()
-                };
-                let res = res
-                    .json::<RunCommandResponse>()
+        } else {
+            tokio::select! {
+                changed = waiter.changed() => changed.unwrap(),
+                _ = &mut exit => break,
+            }
+        }
+    }
+}
+
+/// Sends one command to the primary instance and hands its reply to the waiting caller.
+async fn proxy_command(
+    state: &PackedState,
+    client: &reqwest::Client,
+    primary: &str,
+    entry: ToolArguments,
+    tool_timeout: Duration,
+    no_wait: bool,
+) {
+    let timeout = entry.args.reply_timeout(tool_timeout) + LONG_POLL_DURATION;
+    let res = match send_to_proxy(client, primary, &entry, timeout, no_wait).await {
+        Ok(res) if res.status() == StatusCode::BAD_REQUEST => {
+            let error = match res.json::<ProxyRejection>().await {
+                Ok(rejection) => rejection.error,
+                Err(e) => format!("unreadable rejection: {e}"),
+            };
+            tracing::error!("Primary instance rejected the command: {error}");
+            Err(ToolError::internal(format!(
+                "The instance connected to Studio rejected the command, it may be running a different version of this server: {error}"
+            )))
+        }
+        Ok(res) => match res.json::<RunCommandResponse>().await {
+            Ok(r) => r.into_result(),
+            Err(e) => Err(ToolError::internal(format!("Invalid proxy response: {e}"))),
+        },
+        Err(e) if e.is_timeout() => {
+            tracing::error!("Proxied command timed out: {e:?}");
+            Err(ToolError::new(
+                ToolErrorCode::Timeout,
+                format!("Proxied command timed out: {e}"),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to proxy: {e:?}");
+            Err(ToolError::internal(format!("Failed to proxy: {e}")))
+        }
+    };
+    let id = entry.id.unwrap();
+    if let Some(tx) = state.take_caller(id).await {
+        tx.send(CommandReply::Done(res)).ok();
+    }
+}
+
+async fn wait_for_in_flight(state: &PackedState) -> bool {
+    tokio::time::timeout(SHUTDOWN_GRACE_PERIOD, async {
+        while !state.output_map.lock().await.is_empty() {
+            tokio::time::sleep(SHUTDOWN_POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .is_ok()
+}
+
+/// Stops accepting new tool calls and gives in-flight commands a short grace period to finish.
+/// Whatever is still outstanding afterwards is failed so clients get a clean error instead of EOF.
+pub async fn drain_in_flight(state: PackedState) {
+    state.set_shutting_down();
+    if wait_for_in_flight(&state).await {
+        return;
+    }
+
+    let outstanding = state.take_outstanding().await;
+    tracing::warn!(
+        "Failing {} outstanding commands on shutdown",
+        outstanding.len()
+    );
+    fail_callers(outstanding, ToolError::internal("Server shutting down"));
+    // Let the failed callers pick up their errors before the transport closes.
+    wait_for_in_flight(&state).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Selection as the plugin encodes it, with quotes, escapes, JSON nested in a string and
+    /// characters outside ASCII that a careless JSON hop would mangle.
+    const SELECTION: &str = r#"{"selection":[{"name":"Café \"Door\"","path":"Workspace.Tab\there\\nNot a newline","tag":"🧱 ","control":"\u0001","attributes":"{\"open\":[1,2]}"}]}"#;
+
+    /// Replies of the fake plugin, picked by the instance path of the command.
+    fn plugin_replies(id: Uuid, kind: &str) -> Vec<serde_json::Value> {
+        match kind {
+            "text" => vec![
+                json!({ "id": id, "response": "Selected 1 instance\n", "partial": true }),
+                json!({ "id": id, "response": SELECTION }),
+            ],
+            "error" => vec![json!({
+                "id": id,
+                "response": "Workspace.\"Missing\\\" wasn't found\n",
+                "is_error": true,
+                "error_code": "plugin_error",
+            })],
+            "binary" => vec![json!({
+                "id": id,
+                "response": BASE64.encode([0u8, 159, 146, 150, 255]),
+                "content_type": "image/png",
+            })],
+            _ => unreachable!("no reply for {kind}"),
+        }
+    }
+
+    /// Polls `/request` like the plugin does and answers every command with `plugin_replies`.
+    /// Like the plugin it keeps retrying while the server isn't listening yet.
+    async fn fake_plugin(base: String) {
+        let client = reqwest::Client::new();
+        loop {
+            let Ok(res) = client.get(format!("{base}/request")).send().await else {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                continue;
+            };
+            if res.status() != StatusCode::OK {
+                continue;
+            }
+            let command: ToolArguments = res.json().await.unwrap();
+            let ToolArgumentValues::GetBoundingBox(args) = &command.args else {
+                unreachable!("unexpected command {command:?}");
+            };
+            let kind = args.instance_path.as_deref().unwrap();
+            for reply in plugin_replies(command.id.unwrap(), kind) {
+                client
+                    .post(format!("{base}/response"))
+                    .json(&reply)
+                    .send()
                     .await
-                    .map(|r| r.response)
-                    .map_err(Into::into);
-                tx.send(res).unwrap();
-            } else {
-                tracing::error!("Failed to proxy: {res:?}");
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Starts a primary instance on a free port with the fake plugin connected to it.
+    async fn serve_primary() -> (PackedState, String) {
+        let state = Arc::new(AppState::new());
+        let base = serve(plugin_router(Arc::clone(&state))).await;
+        tokio::spawn(fake_plugin(base.clone()));
+        (state, base)
+    }
+
+    fn command(kind: &str) -> ToolArguments {
+        let args = ToolArgumentValues::GetBoundingBox(GetBoundingBox {
+            instance_path: Some(kind.to_string()),
+        });
+        ToolArguments::new(args, Duration::from_secs(10)).0
+    }
+
+    /// Queues a command the way a tool call does and waits for its reply.
+    async fn run_queued(state: &PackedState, command: ToolArguments) -> ToolResult {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        state.push_command(command, tx, None).await;
+        collect_reply(&mut rx, None).await
+    }
+
+    /// Runs a command the way a tool call on the primary does.
+    async fn run_on_primary(primary: &PackedState, kind: &str) -> ToolResult {
+        run_queued(primary, command(kind)).await
+    }
+
+    /// Starts a secondary instance forwarding its commands to `primary`.
+    fn start_proxy_loop(
+        primary: String,
+    ) -> (
+        PackedState,
+        oneshot::Sender<()>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let secondary = Arc::new(AppState::new());
+        secondary.set_proxying();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let proxy_loop = tokio::spawn(dud_proxy_loop(
+            Arc::clone(&secondary),
+            exit_rx,
+            primary,
+            Duration::from_secs(10),
+            false,
+        ));
+        (secondary, exit_tx, proxy_loop)
+    }
+
+    /// Serves `app` on a free port, returning its base URL.
+    async fn serve(app: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    /// Runs a command the way a tool call on a secondary instance does, through the proxy.
+    async fn run_proxied(primary: &str, kind: &str) -> ToolResult {
+        let secondary = Arc::new(AppState::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        secondary.push_command(command(kind), tx, None).await;
+        let (entry, _) = secondary.pop_command().await.unwrap();
+        let client = reqwest::Client::new();
+        proxy_command(
+            &secondary,
+            &client,
+            primary,
+            entry,
+            Duration::from_secs(10),
+            false,
+        )
+        .await;
+        collect_reply(&mut rx, None).await
+    }
+
+    /// Serialized form of a result, which is equal byte for byte only if the results are.
+    fn wire(result: ToolResult) -> String {
+        serde_json::to_string(&RunCommandResponse::from_result(Uuid::nil(), result)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn proxied_replies_match_the_primary() {
+        let (primary, base) = serve_primary().await;
+
+        let direct = run_on_primary(&primary, "text").await;
+        assert!(
+            matches!(&direct, Ok(ToolOutput::Text(text)) if *text == format!("Selected 1 instance\n{SELECTION}"))
+        );
+
+        for kind in ["text", "error", "binary"] {
+            let direct = wire(run_on_primary(&primary, kind).await);
+            let proxied = wire(run_proxied(&base, kind).await);
+            assert_eq!(
+                direct, proxied,
+                "{kind} reply changed going through the proxy"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn plugin_started_before_the_server_connects() {
+        // Find a free port and let it go, so the plugin first polls a port nothing listens on.
+        let port = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        tokio::spawn(fake_plugin(format!("http://127.0.0.1:{port}")));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let state = Arc::new(AppState::new());
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .unwrap();
+        let app = plugin_router(Arc::clone(&state));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // The plugin's first poll counts as connected before any command or heartbeat exists.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !state.plugin_connected().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the plugin never connected");
+
+        let reply = tokio::time::timeout(Duration::from_secs(5), run_on_primary(&state, "text"))
+            .await
+            .expect("the plugin never picked up the command");
+        assert!(matches!(reply, Ok(ToolOutput::Text(_))));
+    }
+
+    #[tokio::test]
+    async fn proxy_loop_runs_commands_on_the_primary() {
+        let primary = Arc::new(AppState::new());
+        let base = serve(plugin_router(Arc::clone(&primary))).await;
+        tokio::spawn(crate::mock_plugin::run(base.clone()));
+        let (secondary, exit, proxy_loop) = start_proxy_loop(base);
+
+        let args = ToolArgumentValues::RunCode(RunCode {
+            command: "print(#workspace:GetChildren())".to_string(),
+            stream_logs: None,
+        });
+        let (command, _) = ToolArguments::new(args, Duration::from_secs(10));
+        let reply = tokio::time::timeout(Duration::from_secs(10), run_queued(&secondary, command))
+            .await
+            .expect("the proxied command never finished");
+        assert!(
+            matches!(&reply, Ok(ToolOutput::Text(text)) if text == "[OUTPUT] mock run_code output\n"),
+            "unexpected reply {reply:?}"
+        );
+        assert!(secondary.output_map.lock().await.is_empty());
+        assert!(primary.output_map.lock().await.is_empty());
+
+        exit.send(()).unwrap();
+        proxy_loop.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn proxy_loop_reports_a_failed_primary() {
+        // A primary that died and left its port free, and one that fails every command.
+        let port = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let broken = serve(axum::Router::new().route(
+            "/proxy",
+            post(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong") }),
+        ))
+        .await;
+
+        for (primary, expected) in [
+            (format!("http://127.0.0.1:{port}"), "Failed to proxy"),
+            (broken, "Invalid proxy response"),
+        ] {
+            let (secondary, exit, proxy_loop) = start_proxy_loop(primary);
+            let reply = tokio::time::timeout(
+                Duration::from_secs(10),
+                run_queued(&secondary, command("text")),
+            )
+            .await
+            .expect("the proxied command never finished");
+            let Err(err) = reply else {
+                panic!("expected an error, got {reply:?}");
             };
-        } else {
-            waiter.changed().await.unwrap();
+            assert_eq!(err.code, ToolErrorCode::Internal);
+            assert!(err.message.starts_with(expected), "{}", err.message);
+            assert!(secondary.output_map.lock().await.is_empty());
+
+            // The loop survives the failure and still exits cleanly.
+            exit.send(()).unwrap();
+            proxy_loop.await.unwrap();
         }
     }
+
+    #[tokio::test]
+    async fn read_only_commands_leave_the_place_unmodified() {
+        let state = Arc::new(AppState::new());
+        let base = serve(plugin_router(Arc::clone(&state))).await;
+        tokio::spawn(crate::mock_plugin::run(base));
+        let run = |args| run_queued(&state, ToolArguments::new(args, Duration::from_secs(10)).0);
+        let modified = |reply: ToolResult| {
+            let Ok(ToolOutput::Text(text)) = reply else {
+                panic!("unexpected reply {reply:?}");
+            };
+            serde_json::from_str::<serde_json::Value>(&text).unwrap()["modified"] == true
+        };
+
+        run(ToolArgumentValues::Ping(Ping {})).await.unwrap();
+        let reply = run(ToolArgumentValues::GetDirtyState(GetDirtyState {})).await;
+        assert!(!modified(reply), "a read-only call counted as a change");
+        let reply = run(ToolArgumentValues::GetDirtyState(GetDirtyState {})).await;
+        assert!(
+            !modified(reply),
+            "get_dirty_state counted itself as a change"
+        );
+
+        let args = ToolArgumentValues::RunCode(RunCode {
+            command: "workspace.Baseplate:Destroy()".to_string(),
+            stream_logs: None,
+        });
+        run(args).await.unwrap();
+        let reply = run(ToolArgumentValues::GetDirtyState(GetDirtyState {})).await;
+        assert!(modified(reply), "run_code didn't count as a change");
+
+        // Only the run_code call left a waypoint behind.
+        let Ok(ToolOutput::Text(history)) =
+            run(ToolArgumentValues::GetHistory(GetHistory { limit: None })).await
+        else {
+            panic!("get_history failed");
+        };
+        let history: serde_json::Value = serde_json::from_str(&history).unwrap();
+        assert_eq!(
+            history["waypoints"],
+            json!([{ "label": "MCP: RunCode", "timestamp": 0 }])
+        );
+    }
+
+    #[test]
+    fn redacted_logs_leave_out_scripts() {
+        let script = "local secret = \"hunter2\"\nprint(secret)";
+        let args = ToolArgumentValues::RunCode(RunCode {
+            command: script.to_string(),
+            stream_logs: None,
+        });
+        let (command, _) = ToolArguments::new(args, Duration::from_secs(10));
+        let logged = |redact| {
+            format!(
+                "{:?}",
+                LoggedCommand {
+                    command: &command,
+                    redact,
+                }
+            )
+        };
+        assert!(logged(false).contains("print(secret)"));
+        let redacted = logged(true);
+        assert!(!redacted.contains("print(secret)"), "{redacted}");
+        assert!(
+            redacted.contains("<redacted 38 bytes, 2 lines"),
+            "{redacted}"
+        );
+    }
+
+    #[test]
+    fn truncate_response_keeps_short_text() {
+        let mut text = "short".to_string();
+        truncate_response(&mut text, 5);
+        assert_eq!(text, "short");
+    }
+
+    #[test]
+    fn truncate_response_cuts_at_a_char_boundary() {
+        let mut text = "ab🧱cd".to_string();
+        // The limit falls inside the four bytes of the brick, which is dropped whole.
+        truncate_response(&mut text, 4);
+        assert_eq!(text, "ab...[truncated 6 bytes]");
+
+        let mut text = "ab🧱cd".to_string();
+        truncate_response(&mut text, 6);
+        assert_eq!(text, "ab🧱...[truncated 2 bytes]");
+    }
+
+    #[test]
+    fn truncate_response_zero_disables_the_limit() {
+        let mut text = "é".repeat(100);
+        truncate_response(&mut text, 0);
+        assert_eq!(text, "é".repeat(100));
+    }
+
+    /// Validates tool arguments given in the JSON form the client sends, keyed by variant.
+    fn validate(args: serde_json::Value) -> Result<(), ToolError> {
+        serde_json::from_value::<ToolArgumentValues>(args)
+            .unwrap()
+            .validate()
+    }
+
+    #[test]
+    fn validate_checks_ranges_inclusively() {
+        let structure = |max_depth| {
+            validate(json!({
+                "GetProjectStructure": { "detail": "minimal", "max_depth": max_depth }
+            }))
+        };
+        assert!(structure(1).is_ok());
+        assert!(structure(20).is_ok());
+        let err = structure(21).unwrap_err();
+        assert_eq!(err.code, ToolErrorCode::InvalidArguments);
+        assert_eq!(err.message, "max_depth must be between 1 and 20, got 21");
+        assert!(structure(0).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_nan() {
+        assert!(in_range("transparency", Some(f64::NAN), 0.0..=1.0).is_err());
+        assert!(in_range("transparency", None, 0.0..=1.0).is_ok());
+    }
+
+    #[test]
+    fn validate_checks_mode_dependent_fields() {
+        assert!(
+            validate(json!({ "SimulateInput": { "action": "key_press", "key_code": "E" } }))
+                .is_ok()
+        );
+        let err =
+            validate(json!({ "SimulateInput": { "action": "click", "x": 10.0 } })).unwrap_err();
+        assert_eq!(err.message, "x and y are required for click and mouse_move");
+        assert!(
+            validate(json!({ "SimulateInput": { "action": "key_press", "key_code": "" } }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_limits_grid_size() {
+        let grid = |x| {
+            validate(json!({
+                "CreateGrid": {
+                    "counts": { "x": x, "y": 10, "z": 10 },
+                    "spacing": { "x": 4.0, "y": 4.0, "z": 4.0 },
+                    "template": {}
+                }
+            }))
+        };
+        assert!(grid(10).is_ok());
+        let err = grid(11).unwrap_err();
+        assert_eq!(
+            err.message,
+            "The grid would create 1100 parts, at most 1000 are allowed per call"
+        );
+        assert!(grid(0).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_dotted_names() {
+        let suggest = |base_name| {
+            validate(json!({
+                "SuggestName": { "parent_path": "Workspace", "base_name": base_name }
+            }))
+        };
+        assert!(suggest("Part").is_ok());
+        assert!(suggest("").is_err());
+        assert!(suggest("Map.Part").is_err());
+    }
+
+    fn texts(result: &CallToolResult) -> Vec<&str> {
+        result
+            .content
+            .iter()
+            .map(|content| content.as_text().unwrap().text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        // FNV-1a test vectors, the hash must not change between builds or etags go stale.
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("Workspace"), content_hash("Workspace "));
+    }
+
+    #[test]
+    fn etag_reply_is_short_while_unchanged() {
+        let structure = "Workspace\n  Baseplate\n".to_string();
+        let etag = content_hash(&structure);
+
+        let full = etag_reply(structure.clone(), None, 0);
+        assert_eq!(texts(&full), [structure.as_str(), &format!("etag: {etag}")]);
+
+        let stale = etag_reply(structure.clone(), Some("0000000000000000"), 0);
+        assert_eq!(texts(&stale), texts(&full));
+
+        let unchanged = etag_reply(structure, Some(&etag), 0);
+        assert_eq!(texts(&unchanged), [format!("Not modified, etag: {etag}")]);
+    }
+
+    #[test]
+    fn etag_reply_hashes_the_whole_structure() {
+        let structure = "Workspace\n".repeat(10);
+        let etag = content_hash(&structure);
+        let truncated = etag_reply(structure, None, 20);
+        assert_eq!(
+            texts(&truncated),
+            [
+                "Workspace\nWorkspace\n...[truncated 80 bytes]",
+                &format!("etag: {etag}")
+            ]
+        );
+    }
+
+    /// Sandboxed config rooted in a fresh directory holding `inside/` next to an `outside/`
+    /// directory the sandbox must not reach. The directories are removed when `f` returns.
+    async fn with_sandbox<F: Future<Output = ()>>(f: impl FnOnce(ServerConfig, PathBuf) -> F) {
+        let root = std::env::temp_dir().join(format!("rbx-studio-mcp-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("inside/models")).unwrap();
+        std::fs::create_dir_all(root.join("outside")).unwrap();
+        let root = std::fs::canonicalize(root).unwrap();
+        let config = ServerConfig {
+            workdir: root.join("inside"),
+            sandbox_files: true,
+            ..Default::default()
+        };
+        f(config, root.join("outside")).await;
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sandbox_allows_paths_inside_the_workdir() {
+        with_sandbox(|config, _| async move {
+            let workdir = config.workdir.clone();
+            let resolved = config.resolve_file_path("models/door.rbxm").await.unwrap();
+            assert_eq!(resolved, workdir.join("models/door.rbxm"));
+            let resolved = config
+                .resolve_file_path("models/../door.rbxm")
+                .await
+                .unwrap();
+            assert_eq!(resolved, workdir.join("door.rbxm"));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn sandbox_rejects_parent_dir_escapes() {
+        with_sandbox(|config, _| async move {
+            for path in [
+                "../outside/door.rbxm",
+                "models/../../outside/door.rbxm",
+                "..",
+            ] {
+                let err = config.resolve_file_path(path).await.unwrap_err();
+                assert_eq!(err.code, ToolErrorCode::InvalidArguments, "{path}");
+            }
+            let absolute = std::env::temp_dir().join("door.rbxm");
+            assert!(config
+                .resolve_file_path(absolute.to_str().unwrap())
+                .await
+                .is_err());
+        })
+        .await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sandbox_rejects_symlink_escapes() {
+        with_sandbox(|config, outside| async move {
+            let workdir = config.workdir.clone();
+            std::os::unix::fs::symlink(&outside, workdir.join("linked_dir")).unwrap();
+            std::fs::write(outside.join("secret.rbxm"), "").unwrap();
+            std::os::unix::fs::symlink(outside.join("secret.rbxm"), workdir.join("linked.rbxm"))
+                .unwrap();
+            for path in ["linked_dir/door.rbxm", "linked.rbxm"] {
+                let err = config.resolve_file_path(path).await.unwrap_err();
+                assert!(
+                    err.message.contains("outside of the working directory"),
+                    "{path}"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn paths_are_not_checked_without_the_sandbox() {
+        with_sandbox(|config, _| async move {
+            let config = ServerConfig {
+                sandbox_files: false,
+                ..config
+            };
+            let resolved = config
+                .resolve_file_path("../outside/door.rbxm")
+                .await
+                .unwrap();
+            assert_eq!(resolved, config.workdir.join("../outside/door.rbxm"));
+        })
+        .await;
+    }
 }
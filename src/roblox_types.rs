@@ -0,0 +1,222 @@
+use rmcp::schemars::{self, JsonSchema, Schema, SchemaGenerator};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+
+/// Declares a string newtype that only accepts a curated list of Roblox names. The list is
+/// advertised as a JSON schema `enum` so models are steered towards valid values, and anything
+/// else is rejected when the tool arguments are deserialized.
+macro_rules! curated_name {
+    ($(#[$meta:meta])* $name:ident, $description:literal, [$($value:literal),+ $(,)?]) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub const VALUES: &'static [&'static str] = &[$($value),+];
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                if Self::VALUES.contains(&value.as_str()) {
+                    Ok(Self(value))
+                } else {
+                    Err(de::Error::custom(format!(
+                        "unsupported {} '{value}', expected one of: {}",
+                        stringify!($name),
+                        Self::VALUES.join(", ")
+                    )))
+                }
+            }
+        }
+
+        impl JsonSchema for $name {
+            fn inline_schema() -> bool {
+                true
+            }
+
+            fn schema_name() -> Cow<'static, str> {
+                stringify!($name).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                schemars::json_schema!({
+                    "type": "string",
+                    "description": $description,
+                    "enum": Self::VALUES,
+                })
+            }
+        }
+    };
+}
+
+curated_name!(
+    /// Name of a commonly used Roblox class, as accepted by `Instance.new` or `IsA`.
+    ClassName,
+    "Roblox class name",
+    [
+        "Instance",
+        "PVInstance",
+        "BasePart",
+        "Part",
+        "MeshPart",
+        "WedgePart",
+        "CornerWedgePart",
+        "TrussPart",
+        "UnionOperation",
+        "SpawnLocation",
+        "Seat",
+        "VehicleSeat",
+        "Model",
+        "Folder",
+        "Configuration",
+        "LuaSourceContainer",
+        "BaseScript",
+        "Script",
+        "LocalScript",
+        "ModuleScript",
+        "Tool",
+        "Accessory",
+        "Humanoid",
+        "Camera",
+        "Attachment",
+        "Weld",
+        "WeldConstraint",
+        "Motor6D",
+        "HingeConstraint",
+        "RopeConstraint",
+        "SpringConstraint",
+        "Decal",
+        "Texture",
+        "SurfaceAppearance",
+        "Sound",
+        "PointLight",
+        "SpotLight",
+        "SurfaceLight",
+        "ParticleEmitter",
+        "Beam",
+        "Trail",
+        "Fire",
+        "Smoke",
+        "Sparkles",
+        "Highlight",
+        "ProximityPrompt",
+        "ClickDetector",
+        "RemoteEvent",
+        "RemoteFunction",
+        "BindableEvent",
+        "BindableFunction",
+        "StringValue",
+        "NumberValue",
+        "IntValue",
+        "BoolValue",
+        "ObjectValue",
+        "Vector3Value",
+        "CFrameValue",
+        "Color3Value",
+        "GuiObject",
+        "LayerCollector",
+        "ScreenGui",
+        "SurfaceGui",
+        "BillboardGui",
+        "Frame",
+        "ScrollingFrame",
+        "TextLabel",
+        "TextButton",
+        "TextBox",
+        "ImageLabel",
+        "ImageButton",
+        "UIListLayout",
+        "UIGridLayout",
+        "UICorner",
+        "UIStroke",
+        "UIPadding",
+        "UIScale",
+        "UIAspectRatioConstraint",
+    ]
+);
+
+curated_name!(
+    /// Name of a `Enum.Technology` item used for `Lighting.Technology`.
+    LightingTechnology,
+    "Lighting technology",
+    ["Voxel", "Compatibility", "ShadowMap", "Future", "Unified"]
+);
+
+curated_name!(
+    /// Name of a `Enum.RibbonTool` item, the active editing tool in Studio's ribbon.
+    RibbonTool,
+    "Studio editing tool",
+    ["Select", "Move", "Scale", "Rotate", "Transform", "None"]
+);
+
+curated_name!(
+    /// Name of a `Enum.Material` item that can be used for `BasePart.Material`.
+    Material,
+    "Part material",
+    [
+        "Plastic",
+        "SmoothPlastic",
+        "Neon",
+        "Wood",
+        "WoodPlanks",
+        "Marble",
+        "Slate",
+        "Concrete",
+        "Granite",
+        "Brick",
+        "Pebble",
+        "Cobblestone",
+        "Rock",
+        "Sandstone",
+        "Basalt",
+        "CrackedLava",
+        "Limestone",
+        "Pavement",
+        "CorrodedMetal",
+        "DiamondPlate",
+        "Foil",
+        "Metal",
+        "Grass",
+        "LeafyGrass",
+        "Sand",
+        "Fabric",
+        "Snow",
+        "Mud",
+        "Ground",
+        "Asphalt",
+        "Salt",
+        "Ice",
+        "Glacier",
+        "Glass",
+        "ForceField",
+        "Cardboard",
+        "Carpet",
+        "CeramicTiles",
+        "ClayRoofTiles",
+        "RoofShingles",
+        "Leather",
+        "Plaster",
+        "Rubber",
+    ]
+);
+
+curated_name!(
+    /// Name of a `Enum.StreamingIntegrityMode` item for `Workspace.StreamingIntegrityMode`.
+    StreamingIntegrityMode,
+    "Streaming integrity mode",
+    [
+        "Default",
+        "Disabled",
+        "MinimumRadiusPause",
+        "PauseOutsideLoadedArea"
+    ]
+);
+
+curated_name!(
+    /// Name of a `Enum.PhysicsSteppingMethod` item for `Workspace.PhysicsSteppingMethod`.
+    PhysicsSteppingMethod,
+    "Physics stepping method",
+    ["Default", "Fixed", "Adaptive"]
+);
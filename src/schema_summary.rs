@@ -0,0 +1,83 @@
+use rmcp::model::JsonObject;
+use serde_json::Value;
+
+/// Renders the arguments of a tool input schema as one line per field, used to tell the model
+/// what a tool expects when it passes arguments that don't deserialize.
+pub fn describe_arguments(schema: &JsonObject) -> String {
+    let Some(Value::Object(properties)) = schema.get("properties") else {
+        return "This tool takes no arguments".to_string();
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut lines = vec!["Expected arguments:".to_string()];
+    for (name, property) in properties {
+        let requirement = if required.contains(&name.as_str()) {
+            "required"
+        } else {
+            "optional"
+        };
+        let mut line = format!(
+            "- {name} ({}, {requirement})",
+            describe_type(schema, property)
+        );
+        if let Some(description) = property.get("description").and_then(Value::as_str) {
+            line.push_str(": ");
+            line.push_str(description);
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn describe_type(schema: &JsonObject, property: &Value) -> String {
+    if let Some(reference) = property.get("$ref").and_then(Value::as_str) {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        return match schema
+            .get("definitions")
+            .or_else(|| schema.get("$defs"))
+            .and_then(|definitions| definitions.get(name))
+        {
+            // Inline simple definitions such as enums, objects are better referred to by name.
+            Some(definition) if definition.get("properties").is_none() => {
+                describe_type(schema, definition)
+            }
+            _ => name.to_string(),
+        };
+    }
+    if let Some(Value::Array(values)) = property.get("enum") {
+        let values: Vec<String> = values
+            .iter()
+            .map(|value| match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .collect();
+        return format!("one of {}", values.join(" | "));
+    }
+    if let Some(Value::Array(variants)) = property.get("anyOf").or_else(|| property.get("oneOf")) {
+        let variants: Vec<String> = variants
+            .iter()
+            .filter(|variant| variant.get("const") != Some(&Value::Null))
+            .map(|variant| describe_type(schema, variant))
+            .collect();
+        return variants.join(" or ");
+    }
+    match property.get("type") {
+        Some(Value::String(kind)) if kind == "array" => match property.get("items") {
+            Some(items) => format!("array of {}", describe_type(schema, items)),
+            None => "array".to_string(),
+        },
+        Some(Value::String(kind)) => kind.clone(),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|kind| *kind != "null")
+            .collect::<Vec<_>>()
+            .join(" or "),
+        _ => "any".to_string(),
+    }
+}
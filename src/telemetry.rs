@@ -0,0 +1,42 @@
+use color_eyre::eyre::{Result, WrapErr};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::Subscriber;
+use tracing_subscriber::{filter::LevelFilter, registry::LookupSpan, Layer};
+
+/// Builds a tracing layer exporting spans over OTLP/HTTP to `endpoint`, alongside the provider
+/// that has to be shut down on exit to flush the last batch. Spans are exported at info level
+/// regardless of `RUST_LOG`, which only controls what is logged to stderr.
+pub fn otlp_layer<S>(endpoint: &str) -> Result<(impl Layer<S>, SdkTracerProvider)>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .wrap_err_with(|| format!("Could not create an OTLP exporter for {endpoint}"))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(env!("CARGO_PKG_NAME"))
+                .build(),
+        )
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(LevelFilter::INFO);
+    Ok((layer, provider))
+}
+
+/// Flushes spans that haven't been exported yet, the exporter blocks so this runs off the runtime.
+pub async fn shutdown(provider: SdkTracerProvider) {
+    match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Failed to flush OTLP spans: {e}"),
+        Err(e) => tracing::warn!("Failed to flush OTLP spans: {e}"),
+    }
+}